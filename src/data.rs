@@ -4,21 +4,40 @@
 //!
 //! The [`bodies`] module is quite important to design [`super::field::blob::Blob`]s and [`super::field::target::Target`]s.
 //! For this a [`bodies::BodyDefinition`] structure is used that consists of a size, pivot and grid information.
+//! The same structure describes the shapes of the cutter tools via [`bodies::CutterDefinition`].
 //!
 //! The strucuture [`assets::GameAssets`] contains references to every asset that is used throughout the game
 //!
+//! The progress of the player is stored via [`save::SaveGame`] and the options of the game via [`settings::Settings`].
+//! The controls of the player are given by [`bindings::KeyBindings`].
 
 pub mod assets;
+pub mod bindings;
 pub mod bodies;
 pub mod level;
+pub mod save;
+pub mod settings;
 
 pub mod prelude {
     pub use super::bodies::gen_random_tetris_body;
     pub use super::bodies::gen_tetris_body;
+    pub use super::bodies::BlockColor;
     pub use super::bodies::BodyDefinition;
+    pub use super::bodies::CutterDefinition;
+    pub use super::bodies::CutterMode;
+    pub use super::bodies::CutterShape;
+    pub use super::bodies::CutterShapes;
     pub use super::bodies::TetrisBricks;
 
+    pub use super::level::BlobSpawn;
     pub use super::level::Level;
+    pub use super::level::LevelConstraints;
+    pub use super::level::TargetDefinition;
 
     pub use super::assets::GameAssets;
+
+    pub use super::bindings::KeyBindings;
+
+    pub use super::save::SaveGame;
+    pub use super::settings::Settings;
 }
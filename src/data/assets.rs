@@ -1,5 +1,8 @@
-use crate::data::bodies::TetrisBricks;
-use bevy::prelude::*;
+use crate::data::bodies::{BodyDefinition, CutterMode, CutterShapes};
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
 
 use crate::field::tool::Tool;
 use crate::movement::MoveDirection;
@@ -39,6 +42,10 @@ pub struct GameAssets {
 
     pub clicked_button_color: Color,
 
+    pub tool_tint_half_rotation: Color,
+
+    pub tool_tint_rotation_around_tool: Color,
+
     pub tool_move_left: Handle<Image>,
 
     pub tool_move_right: Handle<Image>,
@@ -55,19 +62,8 @@ pub struct GameAssets {
 
     pub tool_stop: Handle<Image>,
 
-    pub tool_cutter_line: Handle<Image>,
-
-    pub tool_cutter_l: Handle<Image>,
-
-    pub tool_cutter_inv_l: Handle<Image>,
-
-    pub tool_cutter_square: Handle<Image>,
-
-    pub tool_cutter_small_t: Handle<Image>,
-
-    pub tool_cutter_stairs_l: Handle<Image>,
-
-    pub tool_cutter_stairs_r: Handle<Image>,
+    /// the icons of the cutter shapes of the current level, see [`GameAssets::load_cutter_images`]
+    pub tool_cutters: Vec<Handle<Image>>,
 
     pub tool_eraser: Handle<Image>,
 
@@ -95,6 +91,8 @@ impl GameAssets {
             selected_button_color: Color::rgba(0.25, 0.55, 0.25, 0.5),
             selected_but_unavailable_button_color: Color::rgba(0.25, 0.35, 0.25, 0.8),
             clicked_button_color: Color::rgba(0.35, 0.75, 0.35, 0.5),
+            tool_tint_half_rotation: Color::rgb(1.0, 0.85, 0.3),
+            tool_tint_rotation_around_tool: Color::rgb(0.4, 0.8, 1.0),
             tool_move_left: asset_server.load("tools/tool_direction_l.png"),
             tool_move_right: asset_server.load("tools/tool_direction_r.png"),
             tool_move_up: asset_server.load("tools/tool_direction_u.png"),
@@ -103,13 +101,12 @@ impl GameAssets {
             tool_rotate_right: asset_server.load("tools/tool_rotate_r.png"),
             tool_play: asset_server.load("tools/tool_play.png"),
             tool_stop: asset_server.load("tools/tool_stop.png"),
-            tool_cutter_line: asset_server.load("tools/tool_tetris_i.png"),
-            tool_cutter_l: asset_server.load("tools/tool_tetris_l.png"),
-            tool_cutter_inv_l: asset_server.load("tools/tool_tetris_l_inv.png"),
-            tool_cutter_square: asset_server.load("tools/tool_tetris_square.png"),
-            tool_cutter_small_t: asset_server.load("tools/tool_tetris_t.png"),
-            tool_cutter_stairs_l: asset_server.load("tools/tool_tetris_z_inv.png"),
-            tool_cutter_stairs_r: asset_server.load("tools/tool_tetris_z.png"),
+            tool_cutters: CutterShapes::default()
+                .0
+                .iter()
+                .filter_map(|shape| shape.image.as_ref())
+                .map(|path| asset_server.load(path.clone()))
+                .collect(),
             tool_eraser: asset_server.load("tools/tool_eraser.png"),
             tool_erase_all: asset_server.load("tools/tool_erase_all.png"),
             font: asset_server.load("fonts/FiraMono-Medium.ttf"),
@@ -125,22 +122,123 @@ impl GameAssets {
                 MoveDirection::Right => &self.tool_move_right,
             },
             Tool::Rotate(d) => match d {
-                RotateDirection::Left => &self.tool_rotate_left,
-                RotateDirection::Right => &self.tool_rotate_right,
-            },
-            Tool::Cutter(b) => match b {
-                TetrisBricks::Square => &self.tool_cutter_square,
-                TetrisBricks::Line => &self.tool_cutter_line,
-                TetrisBricks::L => &self.tool_cutter_l,
-                TetrisBricks::InvL => &self.tool_cutter_inv_l,
-                TetrisBricks::StairsL => &self.tool_cutter_stairs_l,
-                TetrisBricks::StairsR => &self.tool_cutter_stairs_r,
-                TetrisBricks::SmallT => &self.tool_cutter_small_t,
+                RotateDirection::Left | RotateDirection::LeftAroundTool => &self.tool_rotate_left,
+                RotateDirection::Right
+                | RotateDirection::Half
+                | RotateDirection::RightAroundTool => &self.tool_rotate_right,
             },
+            Tool::Cutter(shape, _) => self
+                .tool_cutters
+                .get(shape.0)
+                .unwrap_or(&self.block_tetris_floor),
             Tool::Simulate => &self.tool_play,
             Tool::Reset => &self.tool_stop,
             Tool::Eraser => &self.tool_eraser,
             Tool::EraseAll => &self.tool_erase_all,
+            Tool::Painter(_) => &self.block_blob,
+        }
+    }
+
+    /// Loads the icons of the given cutter shapes. Shapes without an image in the assets folder or with
+    /// a [`CutterMode`] other than [`CutterMode::Full`] get an icon that is generated from their body.
+    pub fn load_cutter_images(
+        &mut self,
+        shapes: &CutterShapes,
+        asset_server: &AssetServer,
+        images: &mut Assets<Image>,
+    ) {
+        self.tool_cutters = shapes
+            .0
+            .iter()
+            .map(|shape| match (&shape.image, shape.mode) {
+                (Some(path), CutterMode::Full) => asset_server.load(path.clone()),
+                (_, mode) => images.add(generate_body_image(&shape.body, mode)),
+            })
+            .collect();
+    }
+
+    /// gets a tint for tool variants that share their image with another variant
+    pub fn get_tool_tint(&self, tool: Tool) -> Color {
+        match tool {
+            Tool::Rotate(RotateDirection::Half) => self.tool_tint_half_rotation,
+            Tool::Rotate(d) if d.is_around_tool() => self.tool_tint_rotation_around_tool,
+            Tool::Painter(color) => color.tint(),
+            _ => Color::WHITE,
         }
     }
 }
+
+/// Generates an icon in the style of the tool images that shows the blocks of the given body, the
+/// color of the blocks depends on the mode of the cutter
+pub fn generate_body_image(body: &BodyDefinition, mode: CutterMode) -> Image {
+    const SIZE: i32 = 64;
+    const BORDER: i32 = 4;
+    let background = [190, 190, 190, 255];
+    let frame = [120, 120, 120, 255];
+    let (block, block_outline) = match mode {
+        CutterMode::Full => ([0, 230, 230, 255], [0, 120, 120, 255]),
+        CutterMode::Partial { .. } => ([240, 160, 0, 255], [130, 80, 0, 255]),
+        CutterMode::Trimmer { .. } => ([230, 40, 40, 255], [120, 20, 20, 255]),
+    };
+
+    let positions = body.get_relative_positions();
+    let min = positions
+        .iter()
+        .copied()
+        .reduce(IVec2::min)
+        .unwrap_or_default();
+    let max = positions
+        .iter()
+        .copied()
+        .reduce(IVec2::max)
+        .unwrap_or_default();
+    let extent = max - min + IVec2::ONE;
+    let cell = ((SIZE - 4 * BORDER) / extent.x.max(extent.y)).min(16);
+    let offset = (IVec2::splat(SIZE) - extent * cell) / 2;
+
+    let mut data = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let on_frame = x < BORDER / 2
+                || y < BORDER / 2
+                || x >= SIZE - BORDER / 2
+                || y >= SIZE - BORDER / 2;
+            let local = IVec2::new(x, y) - offset;
+            let cell_coord = IVec2::new(local.x.div_euclid(cell), local.y.div_euclid(cell));
+            let in_cell = IVec2::new(local.x.rem_euclid(cell), local.y.rem_euclid(cell));
+            let is_block = positions.contains(&(cell_coord + min))
+                && local.x >= 0
+                && local.y >= 0
+                && local.x < extent.x * cell
+                && local.y < extent.y * cell;
+
+            let color = if on_frame {
+                frame
+            } else if is_block {
+                if in_cell.x == 0
+                    || in_cell.y == 0
+                    || in_cell.x == cell - 1
+                    || in_cell.y == cell - 1
+                {
+                    block_outline
+                } else {
+                    block
+                }
+            } else {
+                background
+            };
+            data.extend_from_slice(&color);
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: SIZE as u32,
+            height: SIZE as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
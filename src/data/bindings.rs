@@ -0,0 +1,446 @@
+//! The bindings module contains the [`KeyBindings`] of the [`ToolActions`] that can be changed by the player.
+//!
+//! The bindings are stored in a simple text file with one `action = input, input` line per action, e.g.
+//! `rotate_right = E, Shift+WheelUp`. Keys are given by their [`KeyCode`] name, mouse buttons, mouse wheel
+//! directions and gamepad buttons are prefixed by `Mouse`, `Wheel` and `Pad`, e.g. `MouseLeft`, `WheelUp` and
+//! `PadSouth`. The directions of the gamepad sticks are named like `LeftStickUp`. Inputs that are pressed together
+//! are joined by `+`, e.g. `Control+Z`.
+
+use std::path::PathBuf;
+
+use bevy::{
+    log,
+    prelude::*,
+    reflect::{DynamicEnum, DynamicVariant, TypeInfo, Typed},
+};
+use leafwing_input_manager::{
+    prelude::*,
+    user_input::{InputKind, Modifier},
+};
+
+use crate::input::ToolActions;
+
+/// The deflection of a gamepad stick that presses the stick direction
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// The directions of the gamepad sticks that can be bound like buttons, true for the positive direction of the axis
+const STICK_DIRECTIONS: [(&str, GamepadAxisType, bool); 8] = [
+    ("LeftStickUp", GamepadAxisType::LeftStickY, true),
+    ("LeftStickDown", GamepadAxisType::LeftStickY, false),
+    ("LeftStickLeft", GamepadAxisType::LeftStickX, false),
+    ("LeftStickRight", GamepadAxisType::LeftStickX, true),
+    ("RightStickUp", GamepadAxisType::RightStickY, true),
+    ("RightStickDown", GamepadAxisType::RightStickY, false),
+    ("RightStickLeft", GamepadAxisType::RightStickX, false),
+    ("RightStickRight", GamepadAxisType::RightStickX, true),
+];
+
+/// The device an input is pressed on, binding an input only replaces the inputs of its device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    Keyboard,
+    Mouse,
+    Gamepad,
+}
+
+impl InputDevice {
+    /// gets the device of the input, a chord of a key and a mouse button like `Shift+MouseMiddle` is a mouse input
+    pub fn of(input: &UserInput) -> Self {
+        let raw = input.raw_inputs();
+        if !raw.gamepad_buttons.is_empty() || !raw.axis_data.is_empty() {
+            InputDevice::Gamepad
+        } else if !raw.mouse_buttons.is_empty()
+            || !raw.mouse_wheel.is_empty()
+            || !raw.mouse_motion.is_empty()
+        {
+            InputDevice::Mouse
+        } else {
+            InputDevice::Keyboard
+        }
+    }
+}
+
+/// The bindings of the [`ToolActions`]
+#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
+pub struct KeyBindings {
+    /// the file the bindings are stored in, if not given the bindings are not persisted (e.g. in wasm builds)
+    #[reflect(ignore)]
+    path: Option<PathBuf>,
+
+    #[reflect(ignore)]
+    map: InputMap<ToolActions>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut map = InputMap::default();
+        map.insert(MouseButton::Left, ToolActions::Place)
+            .insert(MouseButton::Right, ToolActions::Erase)
+            .insert(MouseWheelDirection::Up, ToolActions::NextVariant)
+            .insert(MouseWheelDirection::Down, ToolActions::PreviousVariant)
+            .insert(KeyCode::Q, ToolActions::RotateLeft)
+            .insert_modified(
+                Modifier::Shift,
+                MouseWheelDirection::Down,
+                ToolActions::RotateLeft,
+            )
+            .insert(KeyCode::E, ToolActions::RotateRight)
+            .insert_modified(
+                Modifier::Shift,
+                MouseWheelDirection::Up,
+                ToolActions::RotateRight,
+            )
+            .insert(KeyCode::Key1, ToolActions::SelectMove)
+            .insert(KeyCode::Key2, ToolActions::SelectRotate)
+            .insert(KeyCode::Key3, ToolActions::SelectCutter)
+            .insert(KeyCode::Key4, ToolActions::SelectPainter)
+            .insert(KeyCode::Key5, ToolActions::SelectEraser)
+            .insert(KeyCode::Space, ToolActions::PlayPause)
+            .insert(KeyCode::Period, ToolActions::Step)
+            .insert_modified(Modifier::Control, KeyCode::Z, ToolActions::Undo)
+            .insert(KeyCode::T, ToolActions::ToggleTrajectory);
+
+        // the mouse doesn't continue after a level, such that a click on the field or the toolbar doesn't skip it
+        map.insert(KeyCode::Return, ToolActions::Continue)
+            .insert(GamepadButtonType::South, ToolActions::Continue);
+
+        // the keyboard can place tools without a mouse
+        map.insert(KeyCode::Return, ToolActions::Place)
+            .insert(KeyCode::Delete, ToolActions::Erase)
+            .insert(KeyCode::Up, ToolActions::CursorUp)
+            .insert(KeyCode::Down, ToolActions::CursorDown)
+            .insert(KeyCode::Left, ToolActions::CursorLeft)
+            .insert(KeyCode::Right, ToolActions::CursorRight)
+            .insert_modified(Modifier::Shift, KeyCode::Tab, ToolActions::ToolbarPrevious)
+            .insert(KeyCode::Tab, ToolActions::ToolbarNext)
+            .insert_modified(
+                Modifier::Control,
+                KeyCode::Return,
+                ToolActions::ToolbarActivate,
+            );
+
+        // a gamepad can play the game without mouse and keyboard
+        map.insert(GamepadButtonType::South, ToolActions::Place)
+            .insert(GamepadButtonType::West, ToolActions::Erase)
+            .insert(GamepadButtonType::RightTrigger, ToolActions::NextVariant)
+            .insert(GamepadButtonType::LeftTrigger, ToolActions::PreviousVariant)
+            .insert(GamepadButtonType::LeftTrigger2, ToolActions::RotateLeft)
+            .insert(GamepadButtonType::RightTrigger2, ToolActions::RotateRight)
+            .insert(GamepadButtonType::North, ToolActions::PlayPause)
+            .insert(GamepadButtonType::LeftThumb, ToolActions::Step)
+            .insert(GamepadButtonType::Select, ToolActions::Undo)
+            .insert(GamepadButtonType::DPadUp, ToolActions::CursorUp)
+            .insert(
+                stick_direction(GamepadAxisType::LeftStickY, true),
+                ToolActions::CursorUp,
+            )
+            .insert(GamepadButtonType::DPadDown, ToolActions::CursorDown)
+            .insert(
+                stick_direction(GamepadAxisType::LeftStickY, false),
+                ToolActions::CursorDown,
+            )
+            .insert(GamepadButtonType::DPadLeft, ToolActions::CursorLeft)
+            .insert(
+                stick_direction(GamepadAxisType::LeftStickX, false),
+                ToolActions::CursorLeft,
+            )
+            .insert(GamepadButtonType::DPadRight, ToolActions::CursorRight)
+            .insert(
+                stick_direction(GamepadAxisType::LeftStickX, true),
+                ToolActions::CursorRight,
+            )
+            .insert(
+                stick_direction(GamepadAxisType::RightStickY, true),
+                ToolActions::ToolbarPrevious,
+            )
+            .insert(
+                stick_direction(GamepadAxisType::RightStickY, false),
+                ToolActions::ToolbarNext,
+            )
+            .insert(GamepadButtonType::RightThumb, ToolActions::ToolbarActivate);
+
+        KeyBindings { path: None, map }
+    }
+}
+
+impl KeyBindings {
+    /// loads the bindings from the given file, actions that are missing or broken keep their default bindings
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let content = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        Self::parse(path, &content)
+    }
+
+    fn parse(path: Option<PathBuf>, content: &str) -> Self {
+        let mut bindings = KeyBindings {
+            path,
+            map: InputMap::default(),
+        };
+
+        // the lines only conflict with each other, such that bindings can be swapped in the file
+        let mut configured = vec![];
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match bindings.apply(line) {
+                Ok(action) => configured.push(action),
+                Err(err) => log::warn!("Ignoring line '{}' of the key-binding file: {}", line, err),
+            }
+        }
+
+        let defaults = KeyBindings::default();
+        for action in ToolActions::variants().filter(|action| !configured.contains(action)) {
+            for input in defaults.inputs(action) {
+                if bindings.conflict_of(action, &input).is_none() {
+                    bindings.map.insert(input, action);
+                }
+            }
+        }
+        bindings
+    }
+
+    /// stores the bindings in the key-binding file, if any
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = std::fs::write(path, self.serialize()) {
+                log::warn!(
+                    "Couldn't write key-binding file '{}': {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    pub fn input_map(&self) -> &InputMap<ToolActions> {
+        &self.map
+    }
+
+    /// gets the inputs that are bound to the action
+    pub fn inputs(&self, action: ToolActions) -> Vec<UserInput> {
+        self.map.get(action).cloned().unwrap_or_default()
+    }
+
+    /// gets an action that the input is bound to and that conflicts with the given action, if any
+    pub fn conflict_of(&self, action: ToolActions, input: &UserInput) -> Option<ToolActions> {
+        ToolActions::variants()
+            .filter(|other| action.conflicts_with(*other))
+            .find(|other| self.inputs(*other).contains(input))
+    }
+
+    /// binds the input to the action in place of the inputs of the same [`InputDevice`], the inputs of the other
+    /// devices are kept. Fails with the conflicting action if the input is already bound to another action.
+    pub fn bind(&mut self, action: ToolActions, input: UserInput) -> Result<(), ToolActions> {
+        match self.conflict_of(action, &input) {
+            Some(other) => Err(other),
+            None => {
+                let device = InputDevice::of(&input);
+                for bound in self.inputs(action) {
+                    if InputDevice::of(&bound) == device {
+                        self.map.remove(action, bound);
+                    }
+                }
+                self.map.insert(input, action);
+                Ok(())
+            }
+        }
+    }
+
+    /// restores the default bindings of all actions
+    pub fn reset(&mut self) {
+        self.map = KeyBindings::default().map;
+    }
+
+    /// applies an `action = input, input` line as it is given in the key-binding file and returns its action, the
+    /// line fails if one of its inputs is already bound to another action
+    pub fn apply(&mut self, line: &str) -> Result<ToolActions, String> {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' is no action = input pair", line))?;
+        let (key, value) = (key.trim(), value.trim());
+        let action = ToolActions::variants()
+            .find(|action| action.key() == key)
+            .ok_or_else(|| format!("unknown action '{}'", key))?;
+
+        let inputs = value
+            .split(',')
+            .filter(|input| !input.trim().is_empty())
+            .map(parse_input)
+            .collect::<Result<Vec<_>, _>>()?;
+        for input in inputs.iter() {
+            if let Some(other) = self.conflict_of(action, input) {
+                return Err(format!(
+                    "{} is already bound to '{}'",
+                    format_input(input),
+                    other.key()
+                ));
+            }
+        }
+
+        self.map.clear_action(action);
+        for input in inputs {
+            self.map.insert(input, action);
+        }
+        Ok(action)
+    }
+
+    /// gets the inputs of the action as they are shown in the controls menu
+    pub fn describe(&self, action: ToolActions) -> String {
+        let inputs = self.inputs(action);
+        if inputs.is_empty() {
+            "Unbound".to_string()
+        } else {
+            inputs
+                .iter()
+                .map(format_input)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+
+    fn serialize(&self) -> String {
+        ToolActions::variants()
+            .map(|action| {
+                let inputs: Vec<_> = self.inputs(action).iter().map(format_input).collect();
+                format!("{} = {}\n", action.key(), inputs.join(", "))
+            })
+            .collect()
+    }
+}
+
+/// gets the name of the input as it is written to the key-binding file
+pub fn format_input(input: &UserInput) -> String {
+    match input {
+        UserInput::Single(kind) => format_input_kind(kind),
+        UserInput::Chord(kinds) => kinds
+            .iter()
+            .map(format_input_kind)
+            .collect::<Vec<_>>()
+            .join("+"),
+        // virtual axes are not bound by the player
+        _ => input.to_string(),
+    }
+}
+
+fn format_input_kind(kind: &InputKind) -> String {
+    match kind {
+        InputKind::Mouse(button) => format!("Mouse{:?}", button),
+        InputKind::MouseWheel(direction) => format!("Wheel{:?}", direction),
+        InputKind::GamepadButton(button) => format!("Pad{:?}", button),
+        InputKind::SingleAxis(axis) => STICK_DIRECTIONS
+            .iter()
+            .find(|(_, axis_type, positive)| stick_direction(*axis_type, *positive) == *axis)
+            .map_or_else(|| kind.to_string(), |(name, _, _)| name.to_string()),
+        _ => kind.to_string(),
+    }
+}
+
+/// parses an input of the key-binding file, see the module documentation for the names of the inputs
+pub fn parse_input(input: &str) -> Result<UserInput, String> {
+    let kinds = input
+        .split('+')
+        .map(|name| parse_input_kind(name.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match kinds.as_slice() {
+        [kind] => Ok(UserInput::Single(*kind)),
+        _ => Ok(UserInput::Chord(kinds)),
+    }
+}
+
+fn parse_input_kind(name: &str) -> Result<InputKind, String> {
+    let stick = STICK_DIRECTIONS
+        .iter()
+        .find(|(stick_name, _, _)| *stick_name == name);
+    let kind = if let Some((_, axis_type, positive)) = stick {
+        Some(InputKind::SingleAxis(stick_direction(
+            *axis_type, *positive,
+        )))
+    } else if let Some(modifier) = parse_variant::<Modifier>(name) {
+        Some(InputKind::Modifier(modifier))
+    } else if let Some(button) = name.strip_prefix("Mouse") {
+        parse_variant::<MouseButton>(button).map(InputKind::Mouse)
+    } else if let Some(direction) = name.strip_prefix("Wheel") {
+        parse_variant::<MouseWheelDirection>(direction).map(InputKind::MouseWheel)
+    } else if let Some(button) = name.strip_prefix("Pad") {
+        parse_variant::<GamepadButtonType>(button).map(InputKind::GamepadButton)
+    } else {
+        parse_variant::<KeyCode>(name).map(InputKind::Keyboard)
+    };
+    kind.ok_or_else(|| format!("unknown input '{}'", name))
+}
+
+/// gets the direction of the stick axis as an input that is pressed like a button
+fn stick_direction(axis_type: GamepadAxisType, positive: bool) -> SingleAxis {
+    if positive {
+        SingleAxis::positive_only(axis_type, STICK_THRESHOLD)
+    } else {
+        SingleAxis::negative_only(axis_type, -STICK_THRESHOLD)
+    }
+}
+
+/// gets the unit variant of the enum with the given name, e.g. `KeyCode::Z` for `Z`
+fn parse_variant<T: FromReflect + Typed>(name: &str) -> Option<T> {
+    let TypeInfo::Enum(info) = T::type_info() else {
+        return None;
+    };
+    let variant = info
+        .variant_names()
+        .iter()
+        .find(|variant| **variant == name)?;
+    T::from_reflect(&DynamicEnum::new(*variant, DynamicVariant::Unit))
+}
+
+#[test]
+fn test_key_bindings_roundtrip() {
+    let mut bindings = KeyBindings::default();
+    assert_eq!(
+        bindings.bind(ToolActions::Undo, parse_input("Q").unwrap()),
+        Err(ToolActions::RotateLeft)
+    );
+    bindings
+        .bind(ToolActions::Undo, parse_input("Control+Y").unwrap())
+        .unwrap();
+    bindings.apply("step = PadEast, Shift+MouseMiddle").unwrap();
+    // only the keyboard input of the action is replaced
+    assert_eq!(bindings.describe(ToolActions::Undo), "PadSelect, Control+Y");
+    // a chord with a mouse button is a mouse input
+    bindings
+        .bind(ToolActions::Step, parse_input("MouseMiddle").unwrap())
+        .unwrap();
+    assert_eq!(bindings.describe(ToolActions::Step), "PadEast, MouseMiddle");
+
+    let mut parsed = KeyBindings::parse(None, &bindings.serialize());
+    for action in ToolActions::variants() {
+        assert_eq!(parsed.inputs(action), bindings.inputs(action));
+    }
+
+    assert!(parsed.apply("place = E").is_err());
+    assert!(parsed.apply("place = Banana").is_err());
+    assert!(parsed.apply("unknown = E").is_err());
+    assert_eq!(
+        parsed.describe(ToolActions::CursorLeft),
+        "Left, PadDPadLeft, LeftStickLeft"
+    );
+
+    // swapped bindings do not conflict with the defaults, missing actions keep them
+    let swapped = KeyBindings::parse(None, "place = MouseRight\nerase = MouseLeft\n");
+    assert_eq!(swapped.describe(ToolActions::Place), "MouseRight");
+    assert_eq!(swapped.describe(ToolActions::Erase), "MouseLeft");
+    assert_eq!(swapped.describe(ToolActions::Undo), "Control+Z, PadSelect");
+
+    // the continue action shares its inputs with the place action but not with the mouse
+    assert_eq!(swapped.describe(ToolActions::Continue), "Return, PadSouth");
+    let mut shared = KeyBindings::default();
+    assert_eq!(
+        shared.bind(ToolActions::Continue, parse_input("Space").unwrap()),
+        Ok(())
+    );
+    assert_eq!(shared.describe(ToolActions::PlayPause), "Space, PadNorth");
+
+    assert_eq!(
+        parse_input("WheelDown"),
+        Ok(UserInput::Single(InputKind::MouseWheel(
+            MouseWheelDirection::Down
+        )))
+    );
+}
@@ -1,8 +1,13 @@
 //! The bodies are represented by a RxC (row cross column) i32 vector. Hereby the first R elements represent the top row of the body.
-//! ATM only 0 and 1 are used to indicate if the given position is solid or not.
+//! A 0 indicates that the given position is empty, other values are solid and give the [`BlockColor`] of the block.
 //! The tetris stones and [`crate::field::blob::Blob`] objects use 9x9 vectors. The target shape is a 12x10 vector.
+//!
+//! The shapes of the cutter tools are given as [`CutterDefinition`]s, a level lists the shapes that can be used.
 
-use bevy::{prelude::IVec2, reflect::Reflect};
+use bevy::{
+    prelude::{Color, IVec2, Resource},
+    reflect::Reflect,
+};
 use rand::Rng;
 
 /// Defines a body that is used on a grid. It can describe the body of a [`crate::field::blob::Blob`] or
@@ -36,15 +41,249 @@ impl BodyDefinition {
             //~
 
             let x = (idx as i32 % self.size.0 as i32) - self.pivot.0 as i32;
-            let y = (idx as i32 / self.size.1 as i32) - self.pivot.1 as i32;
+            let y = (idx as i32 / self.size.0 as i32) - self.pivot.1 as i32;
 
             reval.push(IVec2 { x, y });
         }
 
         reval
     }
+
+    /// gets the relative positions together with the colors of the blocks, see [`BlockColor`]
+    pub fn get_relative_positions_with_colors(&self) -> Vec<(IVec2, BlockColor)> {
+        let colors = self
+            .block_positions
+            .iter()
+            .filter(|num| **num != 0)
+            .map(|num| BlockColor::try_from(*num).unwrap_or_default());
+        self.get_relative_positions()
+            .into_iter()
+            .zip(colors)
+            .collect()
+    }
+}
+
+/// The color of a block, the values are used in the bodies of blobs and targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
+pub enum BlockColor {
+    #[default]
+    Gray = 1,
+    Red = 2,
+    Green = 3,
+    Blue = 4,
+    Yellow = 5,
+}
+
+impl TryFrom<i32> for BlockColor {
+    type Error = ();
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            x if x == BlockColor::Gray as i32 => Ok(BlockColor::Gray),
+            x if x == BlockColor::Red as i32 => Ok(BlockColor::Red),
+            x if x == BlockColor::Green as i32 => Ok(BlockColor::Green),
+            x if x == BlockColor::Blue as i32 => Ok(BlockColor::Blue),
+            x if x == BlockColor::Yellow as i32 => Ok(BlockColor::Yellow),
+            _ => Err(()),
+        }
+    }
+}
+
+impl BlockColor {
+    pub fn min() -> i32 {
+        BlockColor::Gray as i32
+    }
+
+    pub fn max() -> i32 {
+        BlockColor::Yellow as i32
+    }
+
+    /// the tint that is applied on the sprite of a block, the default color keeps the image as it is
+    pub fn tint(&self) -> Color {
+        match self {
+            BlockColor::Gray => Color::WHITE,
+            BlockColor::Red => Color::rgb(1.0, 0.35, 0.35),
+            BlockColor::Green => Color::rgb(0.4, 1.0, 0.4),
+            BlockColor::Blue => Color::rgb(0.4, 0.55, 1.0),
+            BlockColor::Yellow => Color::rgb(1.0, 0.95, 0.3),
+        }
+    }
+}
+
+/// Defines the shape of a cutter tool.
+///
+/// The pivot of the body is the cell where the tool is placed, so it is always part of the cut.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Reflect)]
+pub struct CutterDefinition {
+    pub name: String,
+
+    pub body: BodyDefinition,
+
+    /// path of the icon in the assets folder, if there is none the icon is generated from the body
+    pub image: Option<String>,
+
+    /// decides when the cutter fires and what happens to the blocks that are cut
+    pub mode: CutterMode,
+}
+
+/// Decides when a cutter fires and what happens to the blocks of the blob that it cuts
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Reflect)]
+pub enum CutterMode {
+    /// every cell of the cutter has to be covered by the blob, the covered blocks form a new blob
+    #[default]
+    Full,
+
+    /// the covered blocks form a new blob as soon as at least `threshold` cells are covered
+    Partial { threshold: usize },
+
+    /// the covered blocks are removed as soon as at least `threshold` cells are covered
+    Trimmer { threshold: usize },
+}
+
+impl CutterMode {
+    /// checks if a cutter with `num_cells` cells fires when `num_covered` of them are covered by a blob
+    pub fn fires(&self, num_covered: usize, num_cells: usize) -> bool {
+        match *self {
+            CutterMode::Full => num_covered > 0 && num_covered == num_cells,
+            CutterMode::Partial { threshold } | CutterMode::Trimmer { threshold } => {
+                num_covered > 0 && num_covered >= threshold
+            }
+        }
+    }
+
+    /// the trimmer removes blocks instead of spawning a cutout blob
+    pub fn is_trimmer(&self) -> bool {
+        matches!(self, CutterMode::Trimmer { .. })
+    }
+}
+
+impl CutterDefinition {
+    pub fn new(name: &str, body: BodyDefinition) -> Self {
+        CutterDefinition {
+            name: name.to_owned(),
+            body,
+            image: None,
+            mode: CutterMode::Full,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: CutterMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn from_tetris(kind: TetrisBricks) -> Self {
+        let (name, image) = match kind {
+            TetrisBricks::Square => ("Square", "tools/tool_tetris_square.png"),
+            TetrisBricks::Line => ("Line", "tools/tool_tetris_i.png"),
+            TetrisBricks::L => ("L", "tools/tool_tetris_l.png"),
+            TetrisBricks::InvL => ("Inverse L", "tools/tool_tetris_l_inv.png"),
+            TetrisBricks::StairsL => ("Stairs Left", "tools/tool_tetris_z_inv.png"),
+            TetrisBricks::StairsR => ("Stairs Right", "tools/tool_tetris_z.png"),
+            TetrisBricks::SmallT => ("T", "tools/tool_tetris_t.png"),
+        };
+
+        CutterDefinition {
+            name: name.to_owned(),
+            body: BodyDefinition::as_blob(gen_tetris_body(kind)),
+            image: Some(image.to_owned()),
+            mode: CutterMode::Full,
+        }
+    }
+
+    /// a cutter that cuts a single block
+    pub fn single() -> Self {
+        Self::new(
+            "Single",
+            BodyDefinition {
+                block_positions: vec![1],
+                size: (1, 1),
+                pivot: (0, 0),
+            },
+        )
+    }
+
+    /// a cutter of two horizontal blocks
+    pub fn domino() -> Self {
+        Self::new(
+            "Domino",
+            BodyDefinition {
+                block_positions: vec![1, 1],
+                size: (2, 1),
+                pivot: (0, 0),
+            },
+        )
+    }
+
+    /// a cutter in the shape of the plus pentomino
+    pub fn pentomino_plus() -> Self {
+        Self::new(
+            "Plus",
+            BodyDefinition {
+                block_positions: vec![
+                    0, 1, 0, //
+                    1, 1, 1, //
+                    0, 1, 0, //
+                ],
+                size: (3, 3),
+                pivot: (1, 1),
+            },
+        )
+    }
+
+    /// a cutter in the shape of the U pentomino
+    pub fn pentomino_u() -> Self {
+        Self::new(
+            "U",
+            BodyDefinition {
+                block_positions: vec![
+                    1, 0, 1, //
+                    1, 1, 1, //
+                ],
+                size: (3, 2),
+                pivot: (1, 1),
+            },
+        )
+    }
+}
+
+/// generates the cutter shapes of the 7 default tetris bricks
+pub fn gen_tetris_cutter_shapes() -> Vec<CutterDefinition> {
+    (TetrisBricks::min()..=TetrisBricks::max())
+        .filter_map(|kind| TetrisBricks::try_from(kind).ok())
+        .map(CutterDefinition::from_tetris)
+        .collect()
+}
+
+/// The cutter shapes that are available in the current level, a [`crate::field::tool::Tool::Cutter`]
+/// refers to them by index.
+#[derive(Resource, Debug, PartialEq, Eq, Clone, Reflect)]
+pub struct CutterShapes(pub Vec<CutterDefinition>);
+
+impl Default for CutterShapes {
+    fn default() -> Self {
+        CutterShapes(gen_tetris_cutter_shapes())
+    }
+}
+
+impl CutterShapes {
+    pub fn get(&self, shape: CutterShape) -> Option<&CutterDefinition> {
+        self.0.get(shape.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
+/// Index of a [`CutterDefinition`] in the [`CutterShapes`] of the level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
+pub struct CutterShape(pub usize);
+
 /// Describes the 7 default tetris bricks
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
 pub enum TetrisBricks {
@@ -216,7 +455,7 @@ pub fn gen_t_body() -> Vec<i32> {
     ]
 }
 
-/// The module contains prototypical level generation methods for start blobs and target areas
+// The module contains prototypical level generation methods for start blobs and target areas
 
 /// generate a 9x9 i32 flag vector that represents the starting blob given the level number
 pub fn gen_blob_body(level: u32) -> Result<Vec<i32>, String> {
@@ -254,6 +493,7 @@ pub fn gen_blob_body(level: u32) -> Result<Vec<i32>, String> {
             0, 0, 0, 0, 0, 0, 0, 0, 0, //
             0, 0, 0, 0, 0, 0, 0, 0, 0, //
         ]),
+        5 => Ok(gen_line_body()),
         _ => Err(format!("Invalid Level number: {}", level)),
     }
 }
@@ -303,6 +543,34 @@ pub fn gen_target_body(level: u32) -> Result<Vec<i32>, String> {
             0, 0, 1, 1, 1, 1, 0, 0, 0, 0, //
             0, 0, 1, 0, 1, 0, 0, 0, 0, 0, //
         ]),
+        4 => Ok(vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 1, 1, 0, 0, 0, 0, 0, 0, //
+            0, 0, 1, 1, 0, 0, 0, 0, 0, 0, //
+            0, 0, 1, 1, 0, 0, 0, 0, 0, 0, //
+            0, 0, 1, 1, 0, 0, 0, 0, 0, 0, //
+        ]),
+        5 => Ok(vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 1, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 1, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 1, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 1, 0, 0, 0, //
+        ]),
         0 => Ok(vec![
             1, 1, 1, 1, 1, 1, 1, 1, 1, 1, //
             1, 1, 1, 1, 1, 1, 1, 1, 1, 1, //
@@ -320,3 +588,28 @@ pub fn gen_target_body(level: u32) -> Result<Vec<i32>, String> {
         _ => Err(format!("Invalid Level number: {}", level)),
     }
 }
+
+#[test]
+fn test_relative_positions_of_non_square_body() {
+    let body = CutterDefinition::pentomino_u().body;
+    assert_eq!(
+        body.get_relative_positions(),
+        vec![
+            IVec2::new(-1, -1),
+            IVec2::new(1, -1),
+            IVec2::new(-1, 0),
+            IVec2::new(0, 0),
+            IVec2::new(1, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_cutter_mode_fires() {
+    assert!(CutterMode::Full.fires(4, 4));
+    assert!(!CutterMode::Full.fires(3, 4));
+    assert!(!CutterMode::Partial { threshold: 2 }.fires(1, 4));
+    assert!(CutterMode::Partial { threshold: 2 }.fires(2, 4));
+    assert!(CutterMode::Trimmer { threshold: 1 }.fires(1, 4));
+    assert!(!CutterMode::Trimmer { threshold: 0 }.fires(0, 4));
+}
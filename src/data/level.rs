@@ -1,4 +1,8 @@
-use crate::data::bodies::TetrisBricks;
+use crate::data::bodies::{
+    gen_line_body, gen_tetris_cutter_shapes, BlockColor, CutterDefinition, CutterMode, CutterShape,
+};
+use crate::data::save::MAX_STARS;
+use crate::field::target::WinCondition;
 use crate::field::tool::Tool;
 use crate::movement::prelude::*;
 
@@ -10,25 +14,178 @@ use bevy::{prelude::*, reflect::Reflect, utils::HashMap};
 pub struct Level {
     pub num: u32,
 
-    pub start_blob: (Vec<i32>, (i32, i32)),
+    /// the blobs of the level, blobs with a later spawn turn drop into the field during the simulation
+    pub blobs: Vec<BlobSpawn>,
 
-    pub target_figure: (Vec<i32>, (i32, i32)),
+    /// the target areas of the level, the win condition decides how many of them have to be fulfilled
+    pub targets: Vec<TargetDefinition>,
+
+    pub win_condition: WinCondition,
+
+    /// optional constraints, if one of them is violated the level is lost
+    pub constraints: LevelConstraints,
 
     pub applicable_tools: HashMap<Tool, usize>,
 
+    /// the shapes that can be selected for the cutter tool
+    pub cutter_shapes: Vec<CutterDefinition>,
+
+    /// the offsets that are tried if a rotation would overlap occupied cells
+    pub kick_table: KickTable,
+
+    /// decides if moving blobs pass, block or push each other
+    pub blob_collision: BlobCollision,
+
+    /// in the endless mode random tetris bricks keep arriving and a score is accumulated until the field overflows
+    pub endless: bool,
+
+    /// if true full rows of dissolved blocks are removed and the dissolved blocks above them move down
+    pub line_clearing: bool,
+
+    /// if true a production field is spawned next to the factory field, blobs that leave the bottom of the
+    /// factory field are transferred to it and stack there
+    pub production_field: bool,
+
+    /// the number of turns in which the level has to be won to earn all stars, see [`Level::stars`]
+    pub par_turns: i32,
+
     level_text: String,
 }
 
+/// Describes a blob of a level and the turn in which it enters the field
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct BlobSpawn {
+    pub body: Vec<i32>,
+
+    pub position: (i32, i32),
+
+    /// the turn in which the blob is spawned, blobs with turn 0 are spawned together with the level
+    pub turn: i32,
+}
+
+impl BlobSpawn {
+    pub fn new(body: Vec<i32>, position: (i32, i32)) -> Self {
+        BlobSpawn {
+            body,
+            position,
+            turn: 0,
+        }
+    }
+
+    pub fn at_turn(mut self, turn: i32) -> Self {
+        self.turn = turn;
+        self
+    }
+}
+
+/// Describes a target area of a level
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct TargetDefinition {
+    pub body: Vec<i32>,
+
+    pub position: (i32, i32),
+
+    /// if given every required cell has to be filled with this color instead of the colors of the body
+    pub color: Option<BlockColor>,
+
+    /// if true the target is placed on the production field instead of the factory field
+    pub on_production_field: bool,
+}
+
+impl TargetDefinition {
+    pub fn new(body: Vec<i32>, position: (i32, i32)) -> Self {
+        TargetDefinition {
+            body,
+            position,
+            color: None,
+            on_production_field: false,
+        }
+    }
+
+    pub fn with_color(mut self, color: BlockColor) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn on_production_field(mut self) -> Self {
+        self.on_production_field = true;
+        self
+    }
+}
+
+/// Optional constraints of a level, the limits of the turns and the cutter are evaluated by
+/// [`crate::game::level_lost_system`], the limit of the tools is enforced by [`crate::input::check_placement`]
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+pub struct LevelConstraints {
+    /// the level is lost if it is not won within this number of turns
+    pub max_turns: Option<i32>,
+
+    /// the maximum number of tools that may be placed on the field at the same time
+    pub max_tools: Option<usize>,
+
+    /// the level can only be won if a cutter has cut at least once
+    pub require_cutter: bool,
+}
+
+impl LevelConstraints {
+    /// gets a description of the violated constraint, if any
+    ///
+    /// # Arguments
+    /// * `num_turn` - The current turn of the level
+    /// * `num_cuts` - The number of times a cutter has cut
+    /// * `all_blobs_settled` - True if no blob is moving anymore, such that no cut can happen anymore
+    pub fn violation(
+        &self,
+        num_turn: i32,
+        num_cuts: usize,
+        all_blobs_settled: bool,
+    ) -> Option<String> {
+        if let Some(max_turns) = self.max_turns.filter(|max| num_turn > *max) {
+            Some(format!("The turn limit of {} turns is exceeded", max_turns))
+        } else if self.require_cutter && num_cuts == 0 && all_blobs_settled {
+            Some("No cutter has been used".to_owned())
+        } else {
+            None
+        }
+    }
+
+    /// checks if another tool may be placed while the given number of tools is placed on the field
+    pub fn allows_tool(&self, num_tools: usize) -> bool {
+        self.max_tools.is_none_or(|max| num_tools < max)
+    }
+
+    /// checks if the constraints allow to win the level
+    pub fn allows_win(&self, num_cuts: usize) -> bool {
+        !self.require_cutter || num_cuts > 0
+    }
+}
+
 impl Level {
+    /// the level number of the endless mode
+    pub const ENDLESS: u32 = 0;
+
+    /// the number of puzzle levels, they are numbered from 1 to this number
+    pub const NUM_LEVELS: u32 = 5;
+
     pub fn get_text(&self) -> &str {
         &self.level_text
     }
 
+    /// gets the stars for winning the level in the given turn, all stars within the par turns and one star less
+    /// for every further par turns that were needed
+    pub fn stars(&self, num_turn: i32) -> u8 {
+        let num_pars = (num_turn - 1).max(0) / self.par_turns.max(1);
+        MAX_STARS.saturating_sub(num_pars.min(MAX_STARS as i32 - 1) as u8)
+    }
+
     pub fn new(num: u32) -> Self {
         match num {
+            Self::ENDLESS => Self::endless(),
             1 => Self::level_01(),
             2 => Self::level_02(),
             3 => Self::level_03(),
+            4 => Self::level_04(),
+            5 => Self::level_05(),
             _ => panic!("Level '{}' not supported yet", num),
         }
     }
@@ -37,7 +194,11 @@ impl Level {
         let mut applicable_tools = HashMap::new();
         applicable_tools.insert(Tool::Rotate(RotateDirection::default()), 1);
         applicable_tools.insert(Tool::Move(MoveDirection::default()), 0);
-        applicable_tools.insert(Tool::Cutter(TetrisBricks::default()), 0);
+        applicable_tools.insert(
+            Tool::Cutter(CutterShape::default(), Orientation::default()),
+            0,
+        );
+        applicable_tools.insert(Tool::Painter(BlockColor::default()), 0);
         Self::level_helper(1, applicable_tools)
     }
 
@@ -45,7 +206,11 @@ impl Level {
         let mut applicable_tools = HashMap::new();
         applicable_tools.insert(Tool::Move(MoveDirection::default()), 2);
         applicable_tools.insert(Tool::Rotate(RotateDirection::default()), 1);
-        applicable_tools.insert(Tool::Cutter(TetrisBricks::default()), 0);
+        applicable_tools.insert(
+            Tool::Cutter(CutterShape::default(), Orientation::default()),
+            0,
+        );
+        applicable_tools.insert(Tool::Painter(BlockColor::default()), 0);
         Self::level_helper(2, applicable_tools)
     }
 
@@ -53,8 +218,79 @@ impl Level {
         let mut applicable_tools = HashMap::new();
         applicable_tools.insert(Tool::Move(MoveDirection::default()), 0);
         applicable_tools.insert(Tool::Rotate(RotateDirection::default()), 1);
-        applicable_tools.insert(Tool::Cutter(TetrisBricks::default()), 1);
-        Self::level_helper(3, applicable_tools)
+        applicable_tools.insert(
+            Tool::Cutter(CutterShape::default(), Orientation::default()),
+            1,
+        );
+        applicable_tools.insert(Tool::Painter(BlockColor::default()), 0);
+        let mut level = Self::level_helper(3, applicable_tools);
+        level.constraints.require_cutter = true;
+        level.cutter_shapes.extend([
+            CutterDefinition::single(),
+            CutterDefinition::domino(),
+            CutterDefinition::pentomino_plus(),
+            CutterDefinition::pentomino_u(),
+            CutterDefinition::domino().with_mode(CutterMode::Partial { threshold: 1 }),
+            CutterDefinition::single().with_mode(CutterMode::Trimmer { threshold: 1 }),
+        ]);
+        level
+    }
+
+    /// the second blob drops in while the first one is still falling
+    pub fn level_04() -> Self {
+        let mut applicable_tools = HashMap::new();
+        applicable_tools.insert(Tool::Move(MoveDirection::default()), 2);
+        applicable_tools.insert(Tool::Rotate(RotateDirection::default()), 0);
+        applicable_tools.insert(
+            Tool::Cutter(CutterShape::default(), Orientation::default()),
+            0,
+        );
+        applicable_tools.insert(Tool::Painter(BlockColor::default()), 0);
+        let mut level = Self::level_helper(4, applicable_tools);
+        level.blobs = vec![
+            BlobSpawn::new(gen_line_body(), (2, -4)),
+            BlobSpawn::new(gen_line_body(), (6, -4)).at_turn(10),
+        ];
+        level
+    }
+
+    /// the target has to be filled with red blocks, such that the gray blob has to be painted on its way
+    pub fn level_05() -> Self {
+        let mut applicable_tools = HashMap::new();
+        applicable_tools.insert(Tool::Move(MoveDirection::default()), 2);
+        applicable_tools.insert(Tool::Rotate(RotateDirection::default()), 0);
+        applicable_tools.insert(
+            Tool::Cutter(CutterShape::default(), Orientation::default()),
+            0,
+        );
+        applicable_tools.insert(Tool::Painter(BlockColor::default()), 1);
+        let mut level = Self::level_helper(5, applicable_tools);
+        level.targets = level
+            .targets
+            .into_iter()
+            .map(|target| target.with_color(BlockColor::Red))
+            .collect();
+        level
+    }
+
+    /// the endless mode has no blobs and targets, the tetris bricks are spawned by
+    /// [`crate::game::contiously_spawn_tetris_at_end`] and stack on the production field
+    pub fn endless() -> Self {
+        let mut applicable_tools = HashMap::new();
+        applicable_tools.insert(Tool::Move(MoveDirection::default()), 10);
+        applicable_tools.insert(Tool::Rotate(RotateDirection::default()), 10);
+        applicable_tools.insert(
+            Tool::Cutter(CutterShape::default(), Orientation::default()),
+            3,
+        );
+        applicable_tools.insert(Tool::Painter(BlockColor::default()), 0);
+        let mut level = Self::level_helper(Self::ENDLESS, applicable_tools);
+        level.blobs.clear();
+        level.targets.clear();
+        level.endless = true;
+        level.line_clearing = true;
+        level.production_field = true;
+        level
     }
 
     fn level_helper(num: u32, applicable_tools: HashMap<Tool, usize>) -> Self {
@@ -62,21 +298,83 @@ impl Level {
             1 => TUT1,
             2 => TUT2,
             3 => TUT3,
+            4 => TUT4,
+            5 => TUT5,
+            Self::ENDLESS => TUT_ENDLESS,
             _ => "NO TEXT FOR LEVEL yet",
         };
 
         Level {
-            start_blob: (
+            blobs: vec![BlobSpawn::new(
                 super::bodies::gen_blob_body(num).expect("Couldn't generate start blob"),
                 (3, -4),
-            ),
-            target_figure: (
+            )],
+            targets: vec![TargetDefinition::new(
                 super::bodies::gen_target_body(num).expect("Couldn't generate target figure"),
                 (0, 12),
-            ),
+            )],
+            win_condition: WinCondition::default(),
+            constraints: LevelConstraints::default(),
             applicable_tools,
+            cutter_shapes: gen_tetris_cutter_shapes(),
+            kick_table: KickTable::default(),
+            blob_collision: BlobCollision::default(),
+            endless: false,
+            line_clearing: false,
+            production_field: false,
+            par_turns: 40,
             level_text: lvl_txt.to_owned(),
             num,
         }
     }
 }
+
+#[test]
+fn test_level_constraints_violation() {
+    let constraints = LevelConstraints {
+        max_turns: Some(10),
+        max_tools: Some(2),
+        require_cutter: true,
+    };
+    assert_eq!(constraints.violation(10, 0, false), None);
+    assert!(constraints.violation(11, 1, false).is_some());
+    assert!(constraints.violation(5, 0, true).is_some());
+    assert_eq!(constraints.violation(5, 1, true), None);
+
+    // the number of tools is limited when they are placed
+    assert!(constraints.allows_tool(1));
+    assert!(!constraints.allows_tool(2));
+    assert!(LevelConstraints::default().allows_tool(100));
+
+    // a test level whose tool budget is smaller than its inventory can't place every tool
+    let mut level = Level::level_02();
+    level.constraints = constraints.clone();
+    let num_tools: usize = level.applicable_tools.values().sum();
+    assert!(level.constraints.allows_tool(num_tools - 2));
+    assert!(!level.constraints.allows_tool(num_tools - 1));
+
+    // without constraints a level can't be lost and is won without a cut
+    let unconstrained = LevelConstraints::default();
+    assert_eq!(unconstrained.violation(1000, 0, true), None);
+    assert!(unconstrained.allows_win(0));
+    assert!(!constraints.allows_win(0));
+    assert!(constraints.allows_win(1));
+}
+
+#[test]
+fn test_level_stars() {
+    let level = Level::level_01();
+    assert_eq!(level.stars(level.par_turns), 3);
+    assert_eq!(level.stars(level.par_turns + 1), 2);
+    assert_eq!(level.stars(level.par_turns * 10), 1);
+}
+
+#[test]
+fn test_levels_are_complete() {
+    for num in 1..=Level::NUM_LEVELS {
+        let level = Level::new(num);
+        assert_eq!(level.num, num);
+        assert!(!level.blobs.is_empty() && !level.targets.is_empty());
+        assert!(level.blobs.iter().any(|blob| blob.turn == 0));
+    }
+}
@@ -0,0 +1,112 @@
+//! The save module stores the progress of the player, i.e. the solved levels and the stars that have been
+//! earned in them.
+//!
+//! The progress is stored in a simple text file with one line per solved level that contains the level number
+//! and the number of stars, e.g. `2 3`.
+
+use std::path::PathBuf;
+
+use bevy::{log, prelude::*, utils::HashMap};
+
+/// The maximum number of stars that can be earned in a level
+pub const MAX_STARS: u8 = 3;
+
+/// The progress of the player over all levels
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+pub struct SaveGame {
+    /// the file the progress is stored in, if not given the progress is not persisted (e.g. in wasm builds)
+    #[reflect(ignore)]
+    path: Option<PathBuf>,
+
+    /// the best number of stars per solved level
+    stars: HashMap<u32, u8>,
+}
+
+impl SaveGame {
+    /// loads the progress from the given file, a missing or broken file starts without progress
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let stars = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default();
+
+        SaveGame { path, stars }
+    }
+
+    /// stores the progress in the save file, if any
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = std::fs::write(path, self.serialize()) {
+                log::warn!("Couldn't write save file '{}': {}", path.display(), err);
+            }
+        }
+    }
+
+    /// records that the level has been solved with the given stars, returns true if that is a new best
+    pub fn record(&mut self, level: u32, stars: u8) -> bool {
+        let stars = stars.min(MAX_STARS);
+        let best = self.stars.entry(level).or_insert(0);
+        if stars > *best {
+            *best = stars;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// gets the best number of stars of a solved level, `None` if the level has not been solved yet
+    pub fn stars(&self, level: u32) -> Option<u8> {
+        self.stars.get(&level).copied()
+    }
+
+    pub fn is_solved(&self, level: u32) -> bool {
+        self.stars.contains_key(&level)
+    }
+
+    /// gets the first level that has not been solved yet, or the last level if all of them are solved
+    pub fn next_unsolved(&self, num_levels: u32) -> u32 {
+        (1..=num_levels)
+            .find(|level| !self.is_solved(*level))
+            .unwrap_or(num_levels)
+    }
+
+    fn parse(content: &str) -> HashMap<u32, u8> {
+        content
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let level = parts.next()?.parse().ok()?;
+                let stars: u8 = parts.next()?.parse().ok()?;
+                Some((level, stars.min(MAX_STARS)))
+            })
+            .collect()
+    }
+
+    fn serialize(&self) -> String {
+        let mut levels: Vec<_> = self.stars.iter().collect();
+        levels.sort();
+        levels
+            .into_iter()
+            .map(|(level, stars)| format!("{} {}\n", level, stars))
+            .collect()
+    }
+}
+
+#[test]
+fn test_save_game_roundtrip() {
+    let mut save = SaveGame::default();
+    assert!(save.record(2, 1));
+    assert!(save.record(2, 3));
+    assert!(!save.record(2, 2));
+    assert!(save.record(1, 5));
+
+    let parsed = SaveGame::parse(&save.serialize());
+    assert_eq!(parsed, save.stars);
+    assert_eq!(save.stars(1), Some(MAX_STARS));
+    assert_eq!(save.next_unsolved(3), 3);
+    assert_eq!(
+        SaveGame::parse("broken\n1 x\n3 2"),
+        HashMap::from_iter([(3, 2)])
+    );
+}
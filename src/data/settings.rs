@@ -0,0 +1,342 @@
+//! The settings module contains the options of the game that can be changed by the player.
+//!
+//! The [`Settings`] are stored in a simple config file with one `key = value` pair per line, e.g.
+//! `resolution = 1400x1000`. The same pairs can be given on the command line to override the config file, the
+//! overrides are not written back to the config file.
+
+use std::{fmt::Display, ops::RangeInclusive, path::PathBuf, str::FromStr};
+
+use bevy::{log, prelude::*, utils::HashMap};
+
+use crate::SECONDS_PER_ROUND;
+
+/// The resolutions that can be chosen in the settings menu
+const RESOLUTIONS: [(f32, f32); 4] = [
+    (1400., 1000.),
+    (1600., 1100.),
+    (1920., 1200.),
+    (2560., 1440.),
+];
+
+/// The turn durations in seconds that can be chosen in the settings menu
+const TURN_DURATIONS: [f32; 4] = [0.25, SECONDS_PER_ROUND, 0.75, 1.0];
+
+/// The animation durations in milliseconds that can be chosen in the settings menu
+const ANIM_DURATIONS: [u64; 4] = [100, 200, 300, 400];
+
+/// The UI scales that can be chosen in the settings menu
+const UI_SCALES: [f64; 4] = [0.75, 1.0, 1.25, 1.5];
+
+/// The valid width and height of the window in logical pixels
+const RESOLUTION_RANGE: RangeInclusive<f32> = 320.0..=16384.0;
+
+/// The valid turn durations in seconds
+const TURN_DURATION_RANGE: RangeInclusive<f32> = 0.05..=10.0;
+
+/// The valid animation durations in milliseconds
+const ANIM_DURATION_RANGE: RangeInclusive<u64> = 10..=5000;
+
+/// The valid UI scales
+const UI_SCALE_RANGE: RangeInclusive<f64> = 0.25..=4.0;
+
+/// The options of the game
+#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
+pub struct Settings {
+    /// the file the settings are stored in, if not given the settings are not persisted (e.g. in wasm builds)
+    #[reflect(ignore)]
+    path: Option<PathBuf>,
+
+    /// the values of the config file for the options that are overridden on the command line, they are saved
+    /// instead of the overrides
+    #[reflect(ignore)]
+    overridden: HashMap<SettingKind, String>,
+
+    /// the resolution of the window in logical pixels
+    pub resolution: (f32, f32),
+
+    /// if true the frame rate is synchronized with the display
+    pub vsync: bool,
+
+    /// the duration of a turn of the simulation in seconds
+    pub seconds_per_round: f32,
+
+    /// the duration of the animations of the blobs in milliseconds
+    pub anim_duration_ms: u64,
+
+    /// the scale factor of the user interface, e.g. the toolbar and the menus
+    pub ui_scale: f64,
+
+    /// if true the predicted trajectories of the blobs are shown while the simulation is paused
+    pub show_trajectory: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            path: None,
+            overridden: HashMap::default(),
+            resolution: RESOLUTIONS[0],
+            vsync: true,
+            seconds_per_round: SECONDS_PER_ROUND,
+            anim_duration_ms: 200,
+            ui_scale: 1.0,
+            show_trajectory: true,
+        }
+    }
+}
+
+/// The options of the [`Settings`] as they are named in the config file and shown in the settings menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum SettingKind {
+    Resolution,
+    VSync,
+    TurnDuration,
+    AnimDuration,
+    UiScale,
+    Trajectory,
+}
+
+impl SettingKind {
+    pub const ALL: [SettingKind; 6] = [
+        SettingKind::Resolution,
+        SettingKind::VSync,
+        SettingKind::TurnDuration,
+        SettingKind::AnimDuration,
+        SettingKind::UiScale,
+        SettingKind::Trajectory,
+    ];
+
+    /// the key of the option in the config file and on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            SettingKind::Resolution => "resolution",
+            SettingKind::VSync => "vsync",
+            SettingKind::TurnDuration => "seconds_per_round",
+            SettingKind::AnimDuration => "anim_duration_ms",
+            SettingKind::UiScale => "ui_scale",
+            SettingKind::Trajectory => "show_trajectory",
+        }
+    }
+
+    /// the label of the option in the settings menu
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingKind::Resolution => "Resolution",
+            SettingKind::VSync => "VSync",
+            SettingKind::TurnDuration => "Turn duration",
+            SettingKind::AnimDuration => "Animations",
+            SettingKind::UiScale => "UI scale",
+            SettingKind::Trajectory => "Trajectory",
+        }
+    }
+}
+
+impl Settings {
+    /// loads the settings from the given file, missing or broken values keep their default
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut settings = Settings {
+            path,
+            ..Default::default()
+        };
+        if let Some(content) = settings
+            .path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+        {
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                if let Err(err) = settings.apply(line) {
+                    log::warn!("Ignoring line '{}' of the config file: {}", line, err);
+                }
+            }
+        }
+        settings
+    }
+
+    /// stores the settings in the config file, if any
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = std::fs::write(path, self.serialize()) {
+                log::warn!("Couldn't write config file '{}': {}", path.display(), err);
+            }
+        }
+    }
+
+    /// applies a `key = value` pair as it is given in the config file and returns its option, values outside of the
+    /// valid range of the option are rejected
+    pub fn apply(&mut self, pair: &str) -> Result<SettingKind, String> {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' is no key = value pair", pair))?;
+        let (key, value) = (key.trim(), value.trim());
+        let kind = SettingKind::ALL
+            .into_iter()
+            .find(|kind| kind.key() == key)
+            .ok_or_else(|| format!("unknown setting '{}'", key))?;
+
+        match kind {
+            SettingKind::Resolution => {
+                let (width, height) = value
+                    .split_once('x')
+                    .ok_or_else(|| format!("'{}' is no resolution like 1400x1000", value))?;
+                self.resolution = (
+                    parse_in(width, RESOLUTION_RANGE)?,
+                    parse_in(height, RESOLUTION_RANGE)?,
+                );
+            }
+            SettingKind::VSync => self.vsync = parse(value)?,
+            SettingKind::TurnDuration => {
+                self.seconds_per_round = parse_in(value, TURN_DURATION_RANGE)?
+            }
+            SettingKind::AnimDuration => {
+                self.anim_duration_ms = parse_in(value, ANIM_DURATION_RANGE)?
+            }
+            SettingKind::UiScale => self.ui_scale = parse_in(value, UI_SCALE_RANGE)?,
+            SettingKind::Trajectory => self.show_trajectory = parse(value)?,
+        }
+        Ok(kind)
+    }
+
+    /// applies a `key = value` pair as it is given on the command line, the value is used until the player changes
+    /// the option but the value of the config file is kept when the settings are saved
+    pub fn apply_override(&mut self, pair: &str) -> Result<SettingKind, String> {
+        let file_values = self.clone();
+        let kind = self.apply(pair)?;
+        self.overridden
+            .entry(kind)
+            .or_insert_with(|| file_values.value(kind));
+        Ok(kind)
+    }
+
+    /// gets the value of the option as it is written to the config file
+    pub fn value(&self, kind: SettingKind) -> String {
+        match kind {
+            SettingKind::Resolution => format!("{}x{}", self.resolution.0, self.resolution.1),
+            SettingKind::VSync => self.vsync.to_string(),
+            SettingKind::TurnDuration => self.seconds_per_round.to_string(),
+            SettingKind::AnimDuration => self.anim_duration_ms.to_string(),
+            SettingKind::UiScale => self.ui_scale.to_string(),
+            SettingKind::Trajectory => self.show_trajectory.to_string(),
+        }
+    }
+
+    /// gets the option with its value as it is shown in the settings menu
+    pub fn describe(&self, kind: SettingKind) -> String {
+        let value = match kind {
+            SettingKind::VSync if self.vsync => "On".to_string(),
+            SettingKind::VSync => "Off".to_string(),
+            SettingKind::Trajectory if self.show_trajectory => "On".to_string(),
+            SettingKind::Trajectory => "Off".to_string(),
+            SettingKind::TurnDuration => format!("{} s", self.seconds_per_round),
+            SettingKind::AnimDuration => format!("{} ms", self.anim_duration_ms),
+            _ => self.value(kind),
+        };
+        format!("{}: {}", kind.label(), value)
+    }
+
+    /// switches the option to its next value in the settings menu, the chosen value replaces an override of the
+    /// command line and is saved
+    pub fn cycle(&mut self, kind: SettingKind) {
+        self.overridden.remove(&kind);
+        match kind {
+            SettingKind::Resolution => self.resolution = next(&RESOLUTIONS, self.resolution),
+            SettingKind::VSync => self.vsync = !self.vsync,
+            SettingKind::TurnDuration => {
+                self.seconds_per_round = next(&TURN_DURATIONS, self.seconds_per_round)
+            }
+            SettingKind::AnimDuration => {
+                self.anim_duration_ms = next(&ANIM_DURATIONS, self.anim_duration_ms)
+            }
+            SettingKind::UiScale => self.ui_scale = next(&UI_SCALES, self.ui_scale),
+            SettingKind::Trajectory => self.show_trajectory = !self.show_trajectory,
+        }
+    }
+
+    fn serialize(&self) -> String {
+        SettingKind::ALL
+            .iter()
+            .map(|kind| {
+                let value = self
+                    .overridden
+                    .get(kind)
+                    .cloned()
+                    .unwrap_or_else(|| self.value(*kind));
+                format!("{} = {}\n", kind.key(), value)
+            })
+            .collect()
+    }
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|err| format!("invalid value '{}': {}", value, err))
+}
+
+/// parses a value that has to be in the given range
+fn parse_in<T: FromStr + PartialOrd + Display>(
+    value: &str,
+    range: RangeInclusive<T>,
+) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    let parsed: T = parse(value)?;
+    if range.contains(&parsed) {
+        Ok(parsed)
+    } else {
+        Err(format!(
+            "value '{}' is not in {}..={}",
+            value.trim(),
+            range.start(),
+            range.end()
+        ))
+    }
+}
+
+/// gets the value after the current one in the given presets, a custom value continues with the first preset
+fn next<T: Copy + PartialEq>(presets: &[T], current: T) -> T {
+    let idx = presets.iter().position(|value| *value == current);
+    presets[idx.map_or(0, |idx| (idx + 1) % presets.len())]
+}
+
+#[test]
+fn test_settings_roundtrip() {
+    let mut settings = Settings::default();
+    settings.cycle(SettingKind::Resolution);
+    settings.cycle(SettingKind::VSync);
+    settings.apply("ui_scale = 1.5").unwrap();
+    assert_eq!(settings.resolution, RESOLUTIONS[1]);
+    assert!(!settings.vsync);
+
+    let mut parsed = Settings::default();
+    for line in settings.serialize().lines() {
+        parsed.apply(line).unwrap();
+    }
+    assert_eq!(parsed, settings);
+
+    assert!(parsed.apply("resolution = 800").is_err());
+    assert!(parsed.apply("unknown = 1").is_err());
+    assert!(parsed.apply("vsync").is_err());
+    assert!(parsed.apply("seconds_per_round = 0").is_err());
+    assert!(parsed.apply("ui_scale = -1").is_err());
+    assert!(parsed.apply("ui_scale = NaN").is_err());
+    assert!(parsed.apply("resolution = 0x0").is_err());
+    assert_eq!(parsed, settings);
+    assert_eq!(next(&UI_SCALES, 3.0), UI_SCALES[0]);
+
+    // overrides of the command line are not saved, unless the player changes the option
+    let mut overridden = settings.clone();
+    overridden.apply_override("vsync = true").unwrap();
+    overridden.apply_override("vsync = true").unwrap();
+    overridden.apply_override("ui_scale = 0.75").unwrap();
+    assert!(overridden.vsync);
+    assert_eq!(overridden.serialize(), settings.serialize());
+    overridden.cycle(SettingKind::UiScale);
+    assert_eq!(overridden.ui_scale, 1.0);
+    assert!(overridden.serialize().contains("ui_scale = 1\n"));
+    assert!(overridden.serialize().contains("vsync = false\n"));
+}
@@ -8,7 +8,7 @@
 
 use std::fmt::Debug;
 
-use bevy::{log, prelude::*};
+use bevy::{log, prelude::*, utils::HashMap};
 use itertools::Itertools;
 
 pub mod blob;
@@ -23,11 +23,16 @@ pub mod prelude {
 
     pub use super::target::Coordinate;
     pub use super::target::Target;
+    pub use super::target::TargetCell;
+    pub use super::target::WinCondition;
 
+    pub use super::tool::BlocksCutEvent;
     pub use super::tool::Tool;
 
     pub use super::Block;
+    pub use super::FactoryFieldTag;
     pub use super::Field;
+    pub use super::ProductionFieldTag;
 
     pub use super::field_element::FieldElement;
     pub use super::field_element::FieldElementKind;
@@ -54,6 +59,14 @@ pub struct Field {
 #[derive(Component, Debug, PartialEq, Eq, Clone, Reflect)]
 pub struct FieldRenderTag {}
 
+/// marks the factory field on which the player places the tools
+#[derive(Component, Debug, Default, PartialEq, Eq, Clone, Reflect)]
+pub struct FactoryFieldTag {}
+
+/// marks the optional production field that receives the blobs that leave the bottom of the factory field
+#[derive(Component, Debug, Default, PartialEq, Eq, Clone, Reflect)]
+pub struct ProductionFieldTag {}
+
 pub type FieldMutator = dyn Fn(&mut Field, (i32, i32), usize);
 
 impl Field {
@@ -92,6 +105,11 @@ impl Field {
         &self.field_state
     }
 
+    /// replaces the cached field state, e.g. by a state that is not generated from the entities of a level
+    pub fn set_field_state(&mut self, field_state: FieldState) {
+        self.field_state = field_state;
+    }
+
     /// This method can be called by systems to update the field state cache
     /// This ensures that at some places where field state is queried a lot it is not regenerated
     /// all the time (once per system should be fine)
@@ -100,12 +118,19 @@ impl Field {
         block_iter: impl Iterator<Item = (Entity, &'a Block)>,
         tool_query: &Query<&Tool>,
         blob_query: &Query<&Blob>,
-        target_query: &Query<&Target>,
+        target_iter: impl Iterator<Item = &'a Target>,
     ) -> &FieldState {
         let old_fieldstate = self.field_state.clone();
         self.field_state = FieldState::new(self.bounds());
 
-        let target = target_query.single();
+        // merge the cells of all targets, the shape of one target wins over forbidden cells of another
+        let mut target_cells: HashMap<IVec2, TargetCell> = HashMap::new();
+        for (pos, cell) in target_iter.flat_map(|target| target.cells()) {
+            let entry = target_cells.entry(pos).or_insert(cell);
+            if cell.priority() > entry.priority() {
+                *entry = cell;
+            }
+        }
 
         for x in self.bounds().0.x..self.bounds().1.x {
             for y in self.bounds().0.y..self.bounds().1.y {
@@ -118,7 +143,7 @@ impl Field {
                     self.field_state.set_element(
                         pos,
                         FieldElement {
-                            is_target: false,
+                            target: None,
                             entity: None,
                             kind: FieldElementKind::OutOfMovableRegion,
                             position: pos,
@@ -126,12 +151,12 @@ impl Field {
                     );
                 } else {
                     // elements that are part of the actual playing field
-                    let is_target = target.occupied_coordinates().contains(&(x, y));
-                    if is_target {
+                    let target = target_cells.get(&pos).copied();
+                    if target.is_some() {
                         self.field_state.set_element(
                             pos,
                             FieldElement {
-                                is_target: true,
+                                target,
                                 entity: None,
                                 kind: FieldElementKind::Empty,
                                 position: pos,
@@ -143,16 +168,16 @@ impl Field {
         }
 
         for (entity, block) in block_iter {
-            let is_target = old_fieldstate
+            let target = self
+                .field_state
                 .get_element(block.position)
-                .as_ref()
-                .map_or(false, |e| e.is_target);
+                .and_then(|e| e.target);
 
             let new_el = if let Some(group) = block.group {
                 // 1. case group of blob
                 if let Ok(_blob) = blob_query.get(group) {
                     FieldElement {
-                        is_target,
+                        target,
                         entity: Some(entity),
                         kind: FieldElementKind::Block(Some(group)),
                         position: block.position,
@@ -160,7 +185,7 @@ impl Field {
                 // 2. cae group of a tool
                 } else if let Ok(_tool) = tool_query.get(group) {
                     FieldElement {
-                        is_target,
+                        target,
                         entity: Some(entity),
                         kind: FieldElementKind::Tool(group),
                         position: block.position,
@@ -172,7 +197,7 @@ impl Field {
                 }
             } else {
                 FieldElement {
-                    is_target,
+                    target,
                     entity: Some(entity),
                     kind: FieldElementKind::Block(None),
                     position: block.position,
@@ -188,12 +213,13 @@ impl Field {
         self.movable_size
     }
 
+    /// removes all tools of the field and returns them together with their pivots
     pub fn remove_all_tools(
         &mut self,
         commands: &mut Commands,
         query: &Query<&Tool, With<GridBody>>,
         query_body: &Query<&GridBody>,
-    ) -> Vec<Tool> {
+    ) -> Vec<(Tool, IVec2)> {
         let state = self.get_field_state();
 
         let buffer: Vec<(Entity, Tool)> = state
@@ -216,7 +242,13 @@ impl Field {
             despawn_tool(commands, id, query_body);
         }
 
-        buffer.iter().map(|&(_, t)| t).collect()
+        buffer
+            .iter()
+            .map(|&(id, t)| {
+                let pivot = query_body.get(id).map_or(IVec2::ZERO, |body| body.pivot);
+                (t, pivot)
+            })
+            .collect()
     }
 }
 
@@ -246,7 +278,10 @@ pub fn field_states_generation_system(
             .iter()
             .filter(|(_, block)| block.field == field_id);
         //log::info!("Blocks on Field {:?} = {}", field_id, iter.count());
-        field.generate_field_state(iter, &query_tool, &query_blob, &query_target);
+        let targets = query_target
+            .iter()
+            .filter(|target| target.is_on_field(field_id));
+        field.generate_field_state(iter, &query_tool, &query_blob, targets);
     }
 }
 
@@ -308,6 +343,9 @@ pub struct Block {
 
     /// Reference to the parent field of this block
     pub field: Entity,
+
+    /// the color of the block, it has to match the color of a colored [`Target`] cell
+    pub color: BlockColor,
 }
 
 impl Block {
@@ -318,9 +356,51 @@ impl Block {
         group_id: Entity,
         field: Entity,
         handle_zero_position: bool,
+    ) -> Vec<Entity> {
+        Self::spawn_colored_blocks(
+            commands,
+            &body_def.get_relative_positions_with_colors(),
+            pivot,
+            group_id,
+            field,
+            handle_zero_position,
+        )
+    }
+
+    /// spawns blocks of the default color at the given positions relative to the pivot
+    pub fn spawn_blocks(
+        commands: &mut Commands,
+        relative_positions: &[IVec2],
+        pivot: IVec2,
+        group_id: Entity,
+        field: Entity,
+        handle_zero_position: bool,
+    ) -> Vec<Entity> {
+        let colored_positions: Vec<(IVec2, BlockColor)> = relative_positions
+            .iter()
+            .map(|&v| (v, BlockColor::default()))
+            .collect();
+        Self::spawn_colored_blocks(
+            commands,
+            &colored_positions,
+            pivot,
+            group_id,
+            field,
+            handle_zero_position,
+        )
+    }
+
+    /// spawns blocks with the given colors at the given positions relative to the pivot
+    pub fn spawn_colored_blocks(
+        commands: &mut Commands,
+        colored_positions: &[(IVec2, BlockColor)],
+        pivot: IVec2,
+        group_id: Entity,
+        field: Entity,
+        handle_zero_position: bool,
     ) -> Vec<Entity> {
         let mut reval = vec![];
-        for v in body_def.get_relative_positions() {
+        for &(v, color) in colored_positions {
             if v == IVec2::ZERO && !handle_zero_position {
                 continue;
             }
@@ -336,6 +416,7 @@ impl Block {
                     group: Some(group_id),
                     relative_position: Some(v),
                     field,
+                    color,
                 })
                 .insert(Name::new(format!("Block {},{}", v.x, v.y)))
                 .id();
@@ -7,6 +7,7 @@ use leafwing_input_manager::prelude::*;
 
 use crate::{data::bodies::BodyDefinition, input::TetrisActionsWASD, state::GameStateLevel};
 
+use crate::movement::KickTable;
 use crate::prelude::*;
 use crate::view::prelude::*;
 #[cfg(feature = "debug")]
@@ -58,21 +59,24 @@ impl GridBody {
     /// # Arguments
     /// * `cutout_blocks` - A vector containing all the entity-ids of the [`Block`]s that form the second blob via cutout
     /// * `new_pivot` - The field space coordinate of pivot of the second blob.
+    /// * `blob` - The [`Blob`] component of the second blob, e.g. a copy of the first blob to keep its movement.
     ///
-    /// The remaining arguments are bevy internals to send spawn, send events and query for individual [`Block`] components
+    /// The remaining arguments are bevy internals to send spawn, send events and query for individual [`Block`] components.
+    /// Returns the entity of the second blob.
     pub fn cutout(
         &mut self,
         cutout_blocks: &Vec<Entity>,
         new_pivot: IVec2,
+        blob: Blob,
         commands: &mut Commands,
         ev_view: &mut EventWriter<ViewUpdate>,
         block_query: &mut Query<&mut Block>,
-    ) {
+    ) -> Entity {
         // todo: what happens when entities argument contains ids that are not part of self.blocks?
 
         // remove blocks from self and spawn new blob
         self.blocks.retain(|el| !cutout_blocks.contains(el));
-        let new_blob_id = spawn_blob_from_cutout(commands, new_pivot, cutout_blocks);
+        let new_blob_id = spawn_blob_from_cutout(commands, new_pivot, cutout_blocks, blob);
 
         // calculate new relative position for blocks
         for block_id in cutout_blocks {
@@ -84,6 +88,16 @@ impl GridBody {
 
         // inform renderer
         ev_view.send(ViewUpdate::BlobCutout(new_blob_id));
+        new_blob_id
+    }
+
+    /// Removes the given blocks from the body and despawns them, returns true if the body is empty afterwards
+    pub fn trim(&mut self, trimmed_blocks: &[Entity], commands: &mut Commands) -> bool {
+        self.blocks.retain(|el| !trimmed_blocks.contains(el));
+        for block_id in trimmed_blocks {
+            commands.entity(*block_id).despawn_recursive();
+        }
+        self.blocks.is_empty()
     }
 
     /// the size is 9x9 fields - we decided for that magic number in one of the early meetings
@@ -95,47 +109,139 @@ impl GridBody {
         r * GridBody::size() + c
     }
 
-    /// Rotates the blob left (counter-clock wise)
+    /// Rotates the blob left (counter-clock wise), see [`GridBody::rotate_around`]
     pub fn rotate_left<'a>(
         &mut self,
-        block_iter: &mut impl Iterator<Item = Mut<'a, Block>>,
+        block_iter: impl Iterator<Item = Mut<'a, Block>>,
+        field: &Field,
+        kicks: &KickTable,
         ev_view: &mut EventWriter<ViewUpdate>,
         id: Entity,
-    ) {
-        for mut block in block_iter.filter(|b| b.group.is_some() && b.group.unwrap() == id) {
-            /*
-            block.relative_position = block.relative_position.map(|rp| IVec2::new(rp.y, -rp.x));
-            */
-            let old_pos = block.relative_position.unwrap_or_default();
-            block.relative_position = Some(rotate_coord(
-                block.relative_position.unwrap(),
-                Rotation::Left,
-            ));
-            block.position = block.relative_position.unwrap_or_default() + self.pivot;
-
-            log::info!(
-                "Rotated from {} to {}",
-                old_pos,
-                block.relative_position.unwrap_or_default()
-            );
-        }
-
-        ev_view.send(ViewUpdate::BlobRotated(id, Rotation::Left))
+    ) -> RotationOutcome {
+        let pivot = self.pivot;
+        self.rotate_around(block_iter, Rotation::Left, pivot, field, kicks, ev_view, id)
     }
 
-    /// Rotates the blob right (clock wise)
+    /// Rotates the blob right (clock wise), see [`GridBody::rotate_around`]
     pub fn rotate_right<'a>(
         &mut self,
         block_iter: impl Iterator<Item = Mut<'a, Block>>,
+        field: &Field,
+        kicks: &KickTable,
+        ev_view: &mut EventWriter<ViewUpdate>,
+        id: Entity,
+    ) -> RotationOutcome {
+        let pivot = self.pivot;
+        self.rotate_around(
+            block_iter,
+            Rotation::Right,
+            pivot,
+            field,
+            kicks,
+            ev_view,
+            id,
+        )
+    }
+
+    /// Calculates the pivot of the blob after it has been rotated around `center` and shifted by `kick`
+    pub fn rotated_pivot(&self, rotation: Rotation, center: IVec2, kick: IVec2) -> IVec2 {
+        center + rotate_coord(self.pivot - center, rotation) + kick
+    }
+
+    /// Searches the first offset of the kick table that lets the rotated blocks fit into the field.
+    /// Returns `None` if the rotation is blocked for every offset.
+    pub fn find_rotation_kick(
+        &self,
+        relative_positions: &[IVec2],
+        rotation: Rotation,
+        center: IVec2,
+        field: &Field,
+        kicks: &KickTable,
+    ) -> Option<IVec2> {
+        kicks.offsets.iter().copied().find(|&kick| {
+            let pivot = self.rotated_pivot(rotation, center, kick);
+            relative_positions
+                .iter()
+                .all(|rp| is_free_for_rotation(field, pivot + rotate_coord(*rp, rotation)))
+        })
+    }
+
+    /// Rotates the blob around the field coordinate `center`.
+    ///
+    /// Before the blocks are touched the target cells are checked against the [`FieldState`]. If they
+    /// are occupied the offsets of the [`KickTable`] are tried and if none fits the rotation is
+    /// cancelled. The renderer is informed about the outcome, a kick also moves the pivot.
+    #[allow(clippy::too_many_arguments)]
+    pub fn rotate_around<'a>(
+        &mut self,
+        block_iter: impl Iterator<Item = Mut<'a, Block>>,
+        rotation: Rotation,
+        center: IVec2,
+        field: &Field,
+        kicks: &KickTable,
         ev_view: &mut EventWriter<ViewUpdate>,
         id: Entity,
-    ) {
-        for mut block in block_iter.filter(|b| b.group.is_some() && b.group.unwrap() == id) {
-            block.relative_position = block.relative_position.map(|rp| IVec2::new(-rp.y, rp.x));
-            block.position = block.relative_position.unwrap_or_default() + self.pivot;
+    ) -> RotationOutcome {
+        let mut blocks: Vec<Mut<'a, Block>> = block_iter.filter(|b| b.group == Some(id)).collect();
+        let relative_positions: Vec<IVec2> = blocks
+            .iter()
+            .map(|b| b.relative_position.unwrap_or_default())
+            .collect();
+
+        let kick = if let Some(kick) =
+            self.find_rotation_kick(&relative_positions, rotation, center, field, kicks)
+        {
+            kick
+        } else {
+            log::info!("Rotation of {:?} blocked at {}", id, center);
+            ev_view.send(ViewUpdate::BlobRotationBlocked(id, rotation));
+            return RotationOutcome::Blocked;
+        };
+
+        let new_pivot = self.rotated_pivot(rotation, center, kick);
+        for block in blocks.iter_mut() {
+            block.relative_position = block.relative_position.map(|rp| rotate_coord(rp, rotation));
+            block.position = block.relative_position.unwrap_or_default() + new_pivot;
         }
 
-        ev_view.send(ViewUpdate::BlobRotated(id, Rotation::Right))
+        ev_view.send(ViewUpdate::BlobRotated(id, rotation));
+        if new_pivot != self.pivot {
+            self.pivot = new_pivot;
+            ev_view.send(ViewUpdate::BlobMoved(id));
+        }
+
+        if kick == IVec2::ZERO {
+            RotationOutcome::Rotated
+        } else {
+            RotationOutcome::Kicked(kick)
+        }
+    }
+}
+
+/// The result of a rotation attempt of a [`GridBody`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationOutcome {
+    /// the body has been rotated in place
+    Rotated,
+    /// the body has been rotated and moved by the given offset of the [`KickTable`]
+    Kicked(IVec2),
+    /// every offset of the [`KickTable`] leads to an overlap, the rotation has been cancelled
+    Blocked,
+}
+
+/// checks if a rotation may move a block to the given field coordinate
+fn is_free_for_rotation(field: &Field, pos: IVec2) -> bool {
+    match field.get_field_state().get_element(pos).map(|el| el.kind) {
+        // blobs are allowed to move over each other
+        Some(FieldElementKind::Empty)
+        | Some(FieldElementKind::Tool(_))
+        | Some(FieldElementKind::Block(Some(_))) => true,
+        // blobs enter the field from the top, so rotating above it is fine
+        Some(FieldElementKind::OutOfMovableRegion) => {
+            pos.y < 0 && pos.x >= 0 && pos.x < field.movable_size.0 as i32
+        }
+        Some(_) => false,
+        None => pos.y < 0,
     }
 }
 
@@ -147,9 +253,6 @@ pub struct Blob {
 
     /// information if the blob is active (receives movement updates, or not) (for pause after cutting)
     pub active: bool,
-
-    /// a cutout blob is not part of the game anymore but only visual, we do want to get rid of it
-    pub cutout: bool,
 }
 
 impl Blob {
@@ -157,15 +260,6 @@ impl Blob {
         Blob {
             movement: IVec2::new(0, 1),
             active: true,
-            cutout: false,
-        }
-    }
-
-    pub fn new_cutout() -> Self {
-        Blob {
-            movement: IVec2::new(0, 1),
-            active: true,
-            cutout: true,
         }
     }
 }
@@ -174,6 +268,7 @@ pub fn spawn_blob_from_cutout(
     commands: &mut Commands,
     position: IVec2,
     blocks: &[Entity],
+    blob: Blob,
 ) -> Entity {
     commands
         .spawn(SpatialBundle::default())
@@ -182,7 +277,7 @@ pub fn spawn_blob_from_cutout(
             blocks: blocks.to_owned(),
             transferred: false,
         })
-        .insert(Blob::new_cutout())
+        .insert(blob)
         .insert(Name::new("Cutout-Blob"))
         .id()
 }
@@ -224,9 +319,15 @@ pub fn spawn_blob_from_body_definition(
 pub fn move_blob_by_input(
     mut query: Query<(&ActionState<TetrisActionsWASD>, &mut GridBody, Entity)>,
     mut query_block: Query<(Entity, &mut Block)>,
+    field_query: Query<&Field, With<FactoryFieldTag>>,
     mut ev_view: EventWriter<ViewUpdate>,
     level_state: Res<GameStateLevel>,
+    kicks: Res<KickTable>,
 ) {
+    let Ok(field) = field_query.get_single() else {
+        return;
+    };
+
     // continue here
     // check if we are in a turn change...
     if level_state.is_new_turn() {
@@ -249,14 +350,14 @@ pub fn move_blob_by_input(
             }
 
             {
-                let mut block_iter = query_block
+                let block_iter = query_block
                     .iter_mut()
                     .map(|(_, block)| block)
                     .filter(|block| block.group == Some(blob_id));
                 if s.pressed(TetrisActionsWASD::LRotate) {
-                    body.rotate_left(&mut block_iter, &mut ev_view, blob_id);
+                    body.rotate_left(block_iter, field, &kicks, &mut ev_view, blob_id);
                 } else if s.pressed(TetrisActionsWASD::RRotate) {
-                    body.rotate_right(&mut block_iter, &mut ev_view, blob_id);
+                    body.rotate_right(block_iter, field, &kicks, &mut ev_view, blob_id);
                 }
             }
 
@@ -1,4 +1,7 @@
 use bevy::prelude::*;
+use std::ops::Range;
+
+use super::target::TargetCell;
 
 /// Enumeration that differentiates between the kinds of field elements
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
@@ -23,8 +26,8 @@ pub enum FieldElementKind {
 /// a element that descirbes a coordinate in the FieldState
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
 pub struct FieldElement {
-    /// flag indicating if that field element is a target for the level
-    pub is_target: bool,
+    /// the kind of target cell of the level at this element, if any
+    pub target: Option<TargetCell>,
 
     /// "most" relating entity, if a tool and a blob occupy the space this contains the tool
     pub entity: Option<Entity>,
@@ -36,6 +39,13 @@ pub struct FieldElement {
     pub position: IVec2,
 }
 
+impl FieldElement {
+    /// checks if the element is a required or optional cell of the target shape
+    pub fn is_target(&self) -> bool {
+        self.target.is_some_and(|cell| cell.is_part_of_shape())
+    }
+}
+
 /// Encapsules the game state of a game field.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Reflect)]
 pub struct FieldState {
@@ -105,6 +115,18 @@ impl FieldState {
         reval
     }
 
+    /// gets the rows in the given range whose elements in the given columns are all dissolved blocks, i.e.
+    /// blocks that are not part of a blob anymore
+    pub fn get_full_rows(&self, columns: Range<i32>, rows: Range<i32>) -> Vec<i32> {
+        rows.filter(|&y| {
+            columns.clone().all(|x| {
+                self.get_element(IVec2::new(x, y))
+                    .is_some_and(|el| el.kind == FieldElementKind::Block(None))
+            })
+        })
+        .collect()
+    }
+
     pub fn are_all_coordinates(
         &self,
         coords: &Vec<IVec2>,
@@ -156,7 +178,7 @@ impl FieldState {
                 entity: None,
                 kind: FieldElementKind::OutOfValidRegion,
                 position: coord,
-                is_target: false,
+                target: None,
             };
             predicate(&el)
         }
@@ -215,3 +237,25 @@ impl<'a> Iterator for FieldElementIterator<'a> {
         res
     }
 }
+
+#[test]
+fn test_full_rows() {
+    let mut state = FieldState::new((IVec2::new(0, -1), IVec2::new(3, 3)));
+    for (x, y) in [
+        (0, 2),
+        (1, 2),
+        (2, 2),
+        (0, 1),
+        (2, 1),
+        (0, -1),
+        (1, -1),
+        (2, -1),
+    ] {
+        let pos = IVec2::new(x, y);
+        let mut el = state.get_element(pos).unwrap();
+        el.kind = FieldElementKind::Block(None);
+        state.set_element(pos, el);
+    }
+    assert_eq!(state.get_full_rows(0..3, 0..3), vec![2]);
+    assert_eq!(state.get_full_rows(0..3, -1..3), vec![-1, 2]);
+}
@@ -1,3 +1,6 @@
+use super::field_element::FieldElementKind;
+use super::Field;
+use crate::data::bodies::BlockColor;
 use bevy::{ecs::system::EntityCommands, prelude::*};
 
 #[cfg(feature = "debug")]
@@ -6,11 +9,18 @@ use bevy_inspector_egui::prelude::*;
 #[cfg_attr(feature = "debug", derive(InspectorOptions))]
 #[derive(Component, Debug, Default, PartialEq, Eq, Clone, Reflect)]
 pub struct Target {
-    /// the body of the target, different values of the i32 represent different colors
+    /// the body of the target, different values of the i32 represent different colors, see [`BlockColor`]
+    /// and [`TargetCell`] for the cells that are optional or have to stay empty
     pub body: Vec<i32>,
 
     /// @todo in respect to what?
     pub coordinate: Option<Coordinate>,
+
+    /// if given every required cell of the target has to be filled with this color
+    pub color: Option<BlockColor>,
+
+    /// the field the target is placed on, if not given the target is placed on every field
+    pub field: Option<Entity>,
 }
 
 #[derive(Component, Debug, Default, PartialEq, Eq, Clone, Copy, Reflect)]
@@ -36,9 +46,40 @@ impl Target {
         Target {
             body,
             coordinate: None,
+            color: None,
+            field: None,
         }
     }
 
+    pub fn is_on_field(&self, field: Entity) -> bool {
+        self.field.is_none_or(|target_field| target_field == field)
+    }
+
+    /// checks if the target has a required cell, a target with optional and forbidden cells only would be
+    /// fulfilled by an empty field
+    pub fn has_required_cell(&self) -> bool {
+        self.cells()
+            .iter()
+            .any(|(_, cell)| matches!(cell, TargetCell::Required(_)))
+    }
+
+    /// checks if every cell of the target is fulfilled on the given field, `color_of` gets the color of a block.
+    /// A target without a required cell is never fulfilled.
+    pub fn is_fulfilled_on(
+        &self,
+        field: &Field,
+        color_of: impl Fn(Entity) -> Option<BlockColor>,
+    ) -> bool {
+        let field_state = field.get_field_state();
+        self.has_required_cell()
+            && self.cells().into_iter().all(|(pos, cell)| {
+                field_state.get_element(pos).is_some_and(|el| {
+                    let color = el.entity.and_then(&color_of);
+                    cell.is_fulfilled(el.kind, color)
+                })
+            })
+    }
+
     pub fn coords_to_idx(r: usize, c: usize) -> usize {
         r * Target::dimensions().0 + c
     }
@@ -47,17 +88,32 @@ impl Target {
         (10, 12)
     }
 
-    /// the function calculates the occupied coordinates in the coordinate system of the
+    /// the function calculates the coordinates of the required and optional cells in the coordinate system of the
     /// parent (coordinate property)
     pub fn occupied_coordinates(&self) -> Vec<(i32, i32)> {
+        self.cells()
+            .into_iter()
+            .filter(|(_, cell)| cell.is_part_of_shape())
+            .map(|(coord, _)| (coord.x, coord.y))
+            .collect()
+    }
+
+    /// gets all cells of the target together with their kind in the coordinate system of the parent, the color of
+    /// the target overrides the colors of the required cells of the body
+    pub fn cells(&self) -> Vec<(IVec2, TargetCell)> {
         let mut reval = Vec::new();
         if let Some(coordinate) = self.coordinate {
             for r in 0..Target::dimensions().1 {
                 for c in 0..Target::dimensions().0 {
-                    if self.body[Target::coords_to_idx(r, c)] != 0 {
+                    if let Some(mut cell) =
+                        TargetCell::from_value(self.body[Target::coords_to_idx(r, c)])
+                    {
+                        if let (TargetCell::Required(_), Some(color)) = (cell, self.color) {
+                            cell = TargetCell::Required(color);
+                        }
                         let c = c as i32 + coordinate.c;
                         let r = r as i32 + coordinate.r;
-                        reval.push((c, r));
+                        reval.push((IVec2::new(c, r), cell));
                     }
                 }
             }
@@ -66,16 +122,97 @@ impl Target {
     }
 }
 
+/// Decides how many of the targets of a level have to be fulfilled to win the level
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
+pub enum WinCondition {
+    /// every target has to be fulfilled
+    #[default]
+    All,
+
+    /// at least the given number of targets has to be fulfilled, `Any(0)` is treated like `Any(1)`
+    Any(usize),
+}
+
+impl WinCondition {
+    pub fn is_met(&self, num_fulfilled: usize, num_targets: usize) -> bool {
+        match *self {
+            WinCondition::All => num_fulfilled == num_targets,
+            WinCondition::Any(n) => num_fulfilled >= n.max(1).min(num_targets),
+        }
+    }
+}
+
+/// The kind of a cell of a [`Target`], it is encoded in the values of the target body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum TargetCell {
+    /// the cell has to be filled with a block of the given color, encoded by the (positive) color value
+    Required(BlockColor),
+
+    /// the cell may be filled or stay empty, encoded by [`TargetCell::OPTIONAL`]
+    Optional,
+
+    /// the cell has to stay empty, encoded by [`TargetCell::FORBIDDEN`]
+    Forbidden,
+}
+
+impl TargetCell {
+    pub const OPTIONAL: i32 = -1;
+
+    pub const FORBIDDEN: i32 = -2;
+
+    /// decodes a value of the target body, 0 means the cell is not part of the target
+    pub fn from_value(value: i32) -> Option<Self> {
+        match value {
+            0 => None,
+            TargetCell::OPTIONAL => Some(TargetCell::Optional),
+            TargetCell::FORBIDDEN => Some(TargetCell::Forbidden),
+            _ => Some(TargetCell::Required(
+                BlockColor::try_from(value).unwrap_or_default(),
+            )),
+        }
+    }
+
+    /// required and optional cells form the shape of the target
+    pub fn is_part_of_shape(&self) -> bool {
+        !matches!(self, TargetCell::Forbidden)
+    }
+
+    /// the priority of the cell if the cells of several targets overlap, the shape of a target wins
+    pub fn priority(&self) -> i32 {
+        match self {
+            TargetCell::Required(_) => 2,
+            TargetCell::Optional => 1,
+            TargetCell::Forbidden => 0,
+        }
+    }
+
+    /// checks if the cell is fulfilled by the given field element kind and the color of its block, if any
+    pub fn is_fulfilled(&self, kind: FieldElementKind, color: Option<BlockColor>) -> bool {
+        match self {
+            // none means the blob is not existing and the blocks are direcly linked to the field
+            TargetCell::Required(required) => {
+                kind == FieldElementKind::Block(None) && color == Some(*required)
+            }
+            TargetCell::Optional => true,
+            TargetCell::Forbidden => !matches!(kind, FieldElementKind::Block(_)),
+        }
+    }
+}
+
 pub fn spawn_target(
     commands: &mut Commands,
     body: Vec<i32>,
     name: &str,
     coord: Option<Coordinate>,
+    color: Option<BlockColor>,
+    field: Option<Entity>,
     adapter: &dyn Fn(&mut EntityCommands),
 ) -> Entity {
     let target = Target {
         body,
         coordinate: coord,
+        color,
+        field,
     };
 
     let mut ec = commands.spawn(SpatialBundle {
@@ -88,3 +225,141 @@ pub fn spawn_target(
 
     id
 }
+
+#[test]
+fn test_win_condition() {
+    assert!(WinCondition::All.is_met(2, 2));
+    assert!(!WinCondition::All.is_met(1, 2));
+    assert!(WinCondition::Any(1).is_met(1, 3));
+    assert!(!WinCondition::Any(2).is_met(1, 3));
+    // more targets are demanded than there are in the level
+    assert!(WinCondition::Any(4).is_met(3, 3));
+    // no level is won without a fulfilled target
+    assert!(!WinCondition::Any(0).is_met(0, 2));
+    assert!(WinCondition::Any(0).is_met(1, 2));
+}
+
+#[test]
+fn test_target_cells() {
+    assert_eq!(TargetCell::from_value(0), None);
+    assert_eq!(
+        TargetCell::from_value(TargetCell::OPTIONAL),
+        Some(TargetCell::Optional)
+    );
+    assert_eq!(
+        TargetCell::from_value(TargetCell::FORBIDDEN),
+        Some(TargetCell::Forbidden)
+    );
+    let required = TargetCell::from_value(BlockColor::Red as i32).unwrap();
+    assert_eq!(required, TargetCell::Required(BlockColor::Red));
+
+    // a required cell needs a dissolved block of its color
+    let dissolved = FieldElementKind::Block(None);
+    let moving = FieldElementKind::Block(Some(Entity::from_raw(1)));
+    assert!(required.is_fulfilled(dissolved, Some(BlockColor::Red)));
+    assert!(!required.is_fulfilled(dissolved, Some(BlockColor::Gray)));
+    assert!(!required.is_fulfilled(moving, Some(BlockColor::Red)));
+    assert!(!required.is_fulfilled(FieldElementKind::Empty, None));
+    // an optional cell may be filled, a forbidden cell has to stay free of blocks
+    assert!(TargetCell::Optional.is_fulfilled(FieldElementKind::Empty, None));
+    assert!(TargetCell::Optional.is_fulfilled(dissolved, Some(BlockColor::Gray)));
+    assert!(TargetCell::Forbidden.is_fulfilled(FieldElementKind::Empty, None));
+    assert!(TargetCell::Forbidden.is_fulfilled(FieldElementKind::Tool(Entity::from_raw(2)), None));
+    assert!(!TargetCell::Forbidden.is_fulfilled(dissolved, Some(BlockColor::Gray)));
+    assert!(!TargetCell::Forbidden.is_fulfilled(moving, Some(BlockColor::Gray)));
+
+    // a target without required cells is never fulfilled, not even by an empty field
+    let mut body = vec![0; Target::dimensions().0 * Target::dimensions().1];
+    body[Target::coords_to_idx(0, 0)] = TargetCell::OPTIONAL;
+    body[Target::coords_to_idx(1, 0)] = TargetCell::FORBIDDEN;
+    let mut target = Target {
+        coordinate: Some((2, 3).into()),
+        ..Target::new(body)
+    };
+    let mut field = Field::as_factory();
+    field.set_field_state(super::field_element::FieldState::new(field.bounds()));
+    assert!(!target.has_required_cell());
+    assert!(!target.is_fulfilled_on(&field, |_| None));
+    target.body[Target::coords_to_idx(0, 0)] = BlockColor::Gray as i32;
+    assert!(target.has_required_cell());
+    assert!(!target.is_fulfilled_on(&field, |_| None));
+}
+
+#[test]
+fn test_target_color_overrides_body() {
+    let mut body = vec![0; Target::dimensions().0 * Target::dimensions().1];
+    body[Target::coords_to_idx(0, 0)] = BlockColor::Gray as i32;
+    body[Target::coords_to_idx(0, 1)] = TargetCell::OPTIONAL;
+    let definition =
+        crate::data::level::TargetDefinition::new(body, (2, 3)).with_color(BlockColor::Red);
+    let target = Target {
+        body: definition.body,
+        coordinate: Some(definition.position.into()),
+        color: definition.color,
+        field: None,
+    };
+
+    let cells = target.cells();
+    assert_eq!(
+        cells,
+        vec![
+            (IVec2::new(2, 3), TargetCell::Required(BlockColor::Red)),
+            (IVec2::new(3, 3), TargetCell::Optional)
+        ]
+    );
+    let required = cells[0].1;
+    assert!(!required.is_fulfilled(FieldElementKind::Block(None), Some(BlockColor::Gray)));
+    assert!(required.is_fulfilled(FieldElementKind::Block(None), Some(BlockColor::Red)));
+}
+
+#[test]
+fn test_target_on_production_field() {
+    use super::field_element::FieldState;
+
+    let factory_id = Entity::from_raw(1);
+    let production_id = Entity::from_raw(2);
+    let mut body = vec![0; Target::dimensions().0 * Target::dimensions().1];
+    body[Target::coords_to_idx(0, 0)] = BlockColor::Red as i32;
+    body[Target::coords_to_idx(0, 1)] = BlockColor::Red as i32;
+    let definition = crate::data::level::TargetDefinition::new(body, (3, 5)).on_production_field();
+    assert!(definition.on_production_field);
+    let target = Target {
+        body: definition.body,
+        coordinate: Some(definition.position.into()),
+        color: None,
+        field: Some(production_id),
+    };
+
+    // the cells of the target are filled with dissolved red blocks on the production field only
+    let factory = Field::as_factory();
+    let mut production = Field::as_production_field();
+    let mut state = FieldState::new(production.bounds());
+    for (idx, (pos, _)) in target.cells().into_iter().enumerate() {
+        let mut element = state.get_element(pos).unwrap();
+        element.kind = FieldElementKind::Block(None);
+        element.entity = Some(Entity::from_raw(10 + idx as u32));
+        state.set_element(pos, element);
+    }
+    production.set_field_state(state);
+    let red = |_| Some(BlockColor::Red);
+    let gray = |_| Some(BlockColor::Gray);
+
+    let fields = [(factory_id, &factory), (production_id, &production)];
+    let is_fulfilled = |target: &Target| {
+        fields
+            .iter()
+            .filter(|(id, _)| target.is_on_field(*id))
+            .all(|(_, field)| target.is_fulfilled_on(field, red))
+    };
+    assert!(!target.is_on_field(factory_id));
+    assert!(is_fulfilled(&target));
+    assert!(!target.is_fulfilled_on(&production, gray));
+    assert!(!target.is_fulfilled_on(&factory, red));
+
+    // a target without a field has to be filled on every field
+    let everywhere = Target {
+        field: None,
+        ..target.clone()
+    };
+    assert!(!is_fulfilled(&everywhere));
+}
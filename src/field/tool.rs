@@ -16,12 +16,15 @@ pub enum Tool {
     Move(MoveDirection),
     /// A rotation tool that also stores the rotation direction for its subject
     Rotate(RotateDirection),
-    /// A cutter tool can be one of the 7 tetris bricks
-    Cutter(TetrisBricks),
+    /// A cutter tool uses one of the [`CutterShapes`] of the level, the default shapes are the 7 tetris bricks.
+    /// The orientation is applied on the shape when the cutter is placed.
+    Cutter(CutterShape, Orientation),
+    /// A painter tool recolors the blocks of blobs that pass over it
+    Painter(BlockColor),
     /// The simulate command is the default tool
     #[default]
     Simulate,
-    /// The reset command stops a simulation but does not change the state of the field
+    /// The reset command restarts the level, the placed tools are removed
     Reset,
     /// The eraser tool can be used to erase tools that are placed on the field
     Eraser,
@@ -34,12 +37,96 @@ impl Tool {
         match self {
             Tool::Move(_) => Tool::Move(MoveDirection::default()),
             Tool::Rotate(_) => Tool::Rotate(RotateDirection::default()),
-            Tool::Cutter(_) => Tool::Cutter(TetrisBricks::default()),
+            Tool::Cutter(_, _) => Tool::Cutter(CutterShape::default(), Orientation::default()),
+            Tool::Painter(_) => Tool::Painter(BlockColor::default()),
             _ => self,
         }
     }
 }
 
+impl Tool {
+    /// gets the name of the variant of the tool, e.g. the direction of a move tool, `None` for tools without variants
+    pub fn variant_name(&self, cutter_shapes: &CutterShapes) -> Option<String> {
+        match *self {
+            Tool::Move(d) => Some(format!("{:?}", d)),
+            Tool::Rotate(d) => Some(
+                match d {
+                    RotateDirection::Left => "Left",
+                    RotateDirection::Right => "Right",
+                    RotateDirection::Half => "Half",
+                    RotateDirection::LeftAroundTool => "Left around tool",
+                    RotateDirection::RightAroundTool => "Right around tool",
+                }
+                .to_string(),
+            ),
+            Tool::Cutter(shape, orientation) => {
+                let name = cutter_shapes
+                    .get(shape)
+                    .map_or_else(|| format!("Shape {}", shape.0), |def| def.name.clone());
+                Some(format!("{}, {}°", name, orientation as i32 * 90))
+            }
+            Tool::Painter(color) => Some(format!("{:?}", color)),
+            _ => None,
+        }
+    }
+
+    /// gets a short description of the rules of the tool
+    pub fn description(&self, cutter_shapes: &CutterShapes) -> String {
+        match *self {
+            Tool::Move(_) => "Changes the direction of a blob whose pivot passes it".to_string(),
+            Tool::Rotate(d) if d.is_around_tool() => {
+                "Rotates a blob around this cell as soon as a block touches it".to_string()
+            }
+            Tool::Rotate(_) => {
+                "Rotates a blob around its pivot when the pivot passes it".to_string()
+            }
+            Tool::Cutter(shape, _) => {
+                match cutter_shapes.get(shape).map(|def| def.mode).unwrap_or_default() {
+                    CutterMode::Full => {
+                        "Cuts the blocks on its cells into a new blob once a blob covers all of them"
+                            .to_string()
+                    }
+                    CutterMode::Partial { threshold } => format!(
+                        "Cuts the blocks on its cells into a new blob once a blob covers {} of them",
+                        threshold
+                    ),
+                    CutterMode::Trimmer { threshold } => format!(
+                        "Removes the blocks on its cells once a blob covers {} of them",
+                        threshold
+                    ),
+                }
+            }
+            Tool::Painter(_) => "Paints the blocks of a blob that pass over it".to_string(),
+            Tool::Simulate => "Starts the simulation of the factory".to_string(),
+            Tool::Reset => "Restarts the level, the placed tools are removed".to_string(),
+            Tool::Eraser => "Removes a placed tool and returns it to the inventory".to_string(),
+            Tool::EraseAll => {
+                "Removes all placed tools and returns them to the inventory".to_string()
+            }
+        }
+    }
+
+    /// gets the cells the tool covers when its pivot is at the coordinate, a cutter covers all cells of its
+    /// rotated shape
+    pub fn cells(&self, coordinate: IVec2, cutter_shapes: &CutterShapes) -> Vec<IVec2> {
+        let mut cells = vec![coordinate];
+        if let Tool::Cutter(shape, orientation) = *self {
+            if let Some(definition) = cutter_shapes.get(shape) {
+                for pos in definition.body.get_relative_positions() {
+                    let cell = coordinate + orientation.apply(pos);
+                    if !cells.contains(&cell) {
+                        cells.push(cell);
+                    }
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// The first value of the cutters, they follow the other tools as every shape takes four values
+const CUTTER_BASE: i32 = 1000;
+
 impl TryFrom<i32> for Tool {
     type Error = ();
 
@@ -52,14 +139,9 @@ impl TryFrom<i32> for Tool {
 
             201 => Ok(Tool::Rotate(RotateDirection::Left)),
             202 => Ok(Tool::Rotate(RotateDirection::Right)),
-
-            301 => Ok(Tool::Cutter(TetrisBricks::Square)),
-            302 => Ok(Tool::Cutter(TetrisBricks::Line)),
-            303 => Ok(Tool::Cutter(TetrisBricks::L)),
-            304 => Ok(Tool::Cutter(TetrisBricks::InvL)),
-            305 => Ok(Tool::Cutter(TetrisBricks::StairsL)),
-            306 => Ok(Tool::Cutter(TetrisBricks::StairsR)),
-            307 => Ok(Tool::Cutter(TetrisBricks::SmallT)),
+            203 => Ok(Tool::Rotate(RotateDirection::Half)),
+            204 => Ok(Tool::Rotate(RotateDirection::LeftAroundTool)),
+            205 => Ok(Tool::Rotate(RotateDirection::RightAroundTool)),
 
             401 => Ok(Tool::Simulate),
 
@@ -68,6 +150,14 @@ impl TryFrom<i32> for Tool {
             601 => Ok(Tool::Eraser),
 
             701 => Ok(Tool::EraseAll),
+
+            801..=805 => Ok(Tool::Painter((value - 800).try_into()?)),
+
+            // four orientations per cutter shape
+            CUTTER_BASE.. => Ok(Tool::Cutter(
+                CutterShape(((value - CUTTER_BASE) / 4) as usize),
+                ((value - CUTTER_BASE) % 4).try_into()?,
+            )),
             _ => Err(()),
         }
     }
@@ -78,11 +168,14 @@ impl From<Tool> for i32 {
         match t {
             Tool::Move(d) => 100 + d as i32,
             Tool::Rotate(d) => 200 + d as i32,
-            Tool::Cutter(brick) => 300 + brick as i32,
+            Tool::Cutter(shape, orientation) => {
+                CUTTER_BASE + shape.0 as i32 * 4 + orientation as i32
+            }
             Tool::Simulate => 401,
             Tool::Reset => 501,
             Tool::Eraser => 601,
             Tool::EraseAll => 701,
+            Tool::Painter(color) => 800 + color as i32,
         }
     }
 }
@@ -92,11 +185,12 @@ impl Display for Tool {
         let name = match self {
             Tool::Move(_) => "Move",
             Tool::Rotate(_) => "Rotate",
-            Tool::Cutter(_) => "Cut",
+            Tool::Cutter(_, _) => "Cut",
             Tool::Simulate => "Play",
-            Tool::Reset => "Pause",
+            Tool::Reset => "Restart",
             Tool::Eraser => "Eraser",
             Tool::EraseAll => "Reset Factory",
+            Tool::Painter(_) => "Paint",
         };
         write!(f, "{}", name)
     }
@@ -115,25 +209,47 @@ pub struct ToolBundle {
     sprite: SpriteBundle,
 }
 
+/// Event that is sent whenever a cutter cuts blocks of a blob
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlocksCutEvent {
+    /// the blob whose blocks have been cut
+    pub blob: Entity,
+
+    /// the number of blocks that have been cut
+    pub num_blocks: usize,
+
+    /// true if the blocks have been removed by a trimmer instead of forming a new blob
+    pub trimmed: bool,
+}
+
 type RealBlobFilter = (Without<Tool>, With<RealBlob>);
 /// system function that applies the cutter tool and thereby the cutout operation on blobs
+#[allow(clippy::too_many_arguments)]
 pub fn apply_cutter_tool(
     mut commands: Commands,
     tool_query: Query<(&Tool, &GridBody)>,
-    mut blob_query: Query<(&mut Blob, &mut GridBody), RealBlobFilter>,
+    mut blob_query: Query<(Entity, &Blob, &mut GridBody), RealBlobFilter>,
     mut block_query: Query<&mut Block>,
     mut ev_view: EventWriter<ViewUpdate>,
-    level_state: Res<GameStateLevel>,
+    mut ev_cut: EventWriter<BlocksCutEvent>,
+    cutter_shapes: Res<CutterShapes>,
+    mut level_state: ResMut<GameStateLevel>,
 ) {
     if !level_state.is_new_turn() {
         return;
     }
     //~
 
-    if let Ok((mut _blob, mut body)) = blob_query.get_single_mut() {
+    // the tools are placed on the factory field, so blobs on the production field are not affected
+    for (blob_id, blob, mut body) in blob_query.iter_mut().filter(|(_, _, b)| !b.transferred) {
         for (tool, tool_body) in tool_query.iter() {
-            if matches!(tool, Tool::Cutter(_)) {
-                // 1. get all block positions of cutter on the field
+            if let Tool::Cutter(shape, _) = tool {
+                let mode = cutter_shapes
+                    .get(*shape)
+                    .map(|definition| definition.mode)
+                    .unwrap_or_default();
+
+                // 1. get all block positions of cutter on the field (already in the orientation of the cutter)
                 let tool_positions: Vec<IVec2> = tool_body
                     .blocks
                     .iter()
@@ -150,29 +266,85 @@ pub fn apply_cutter_tool(
                     .copied()
                     .collect();
 
-                // 3. if blob blocks and tool blocks have the same len perform cutout
-                if blocks_of_blob.len() == tool_positions.len() {
-                    // apply the cutout on the blob body
-                    body.cutout(
+                // 3. the mode of the cutter decides if enough blocks are covered, cutting out the whole blob changes nothing
+                let is_whole_blob = blocks_of_blob.len() == body.blocks.len();
+                if !mode.fires(blocks_of_blob.len(), tool_positions.len())
+                    || (is_whole_blob && !mode.is_trimmer())
+                {
+                    continue;
+                }
+
+                level_state.num_cuts += 1;
+                ev_cut.send(BlocksCutEvent {
+                    blob: blob_id,
+                    num_blocks: blocks_of_blob.len(),
+                    trimmed: mode.is_trimmer(),
+                });
+
+                if mode.is_trimmer() {
+                    if body.trim(&blocks_of_blob, &mut commands) {
+                        commands.entity(blob_id).despawn_recursive();
+                        break;
+                    }
+                } else {
+                    // apply the cutout on the blob body, the new blob keeps the movement of the old one
+                    let new_blob_id = body.cutout(
                         &blocks_of_blob,
                         tool_body.pivot,
+                        blob.clone(),
                         &mut commands,
                         &mut ev_view,
                         &mut block_query,
                     );
+                    commands.entity(new_blob_id).insert(RealBlob {});
                 }
             }
         }
     }
 }
 
+/// system function that recolors the blocks of blobs that pass over a painter tool
+pub fn apply_painter_tools(
+    tool_query: Query<(&Tool, &GridBody)>,
+    blob_query: Query<&GridBody, (With<Blob>, Without<Tool>)>,
+    mut block_query: Query<&mut Block>,
+    mut ev_view: EventWriter<ViewUpdate>,
+    level_state: Res<GameStateLevel>,
+) {
+    if !level_state.is_new_turn() {
+        return;
+    }
+    //~
+
+    let mut painted = vec![];
+    for (tool, tool_body) in tool_query.iter() {
+        if let Tool::Painter(color) = *tool {
+            for body in blob_query.iter().filter(|body| !body.transferred) {
+                for &block_id in body.blocks.iter() {
+                    if let Ok(mut block) = block_query.get_mut(block_id) {
+                        if block.position == tool_body.pivot && block.color != color {
+                            block.color = color;
+                            painted.push(block_id);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if !painted.is_empty() {
+        ev_view.send(ViewUpdate::BlocksPainted(painted));
+    }
+}
+
 pub fn apply_movement_tools(
-    field_query: Query<&Field>,
+    field_query: Query<&Field, With<FactoryFieldTag>>,
     query_tool: Query<&Tool, Without<Blob>>,
     mut query: Query<(Entity, &mut Blob, &mut GridBody)>,
     mut block_query: Query<(Entity, &mut Block)>,
     mut ev_view: EventWriter<ViewUpdate>,
     level_state: Res<GameStateLevel>,
+    kicks: Res<KickTable>,
 ) {
     if !level_state.is_new_turn() {
         return;
@@ -187,34 +359,45 @@ pub fn apply_movement_tools(
     //~
 
     let state = field.get_field_state();
+    let tool_at = |pos: IVec2| match state.get_element(pos).map(|el| el.kind) {
+        Some(FieldElementKind::Tool(tool_entity)) => query_tool.get(tool_entity).ok().copied(),
+        _ => None,
+    };
 
-    // apply tool if a tool is applied over the pivot
+    // apply tool if a tool is applied over the pivot, rotators around the tool cell react on every block
     query
         .iter_mut()
-        .filter(|e| !e.1.cutout && e.1.active)
+        .filter(|e| e.1.active && !e.2.transferred)
         .for_each(|(blob_id, mut blob, mut body)| {
-            if let Some(element) = state.get_element(body.pivot) {
-                if let FieldElementKind::Tool(tool_entity) = element.kind {
-                    let tool = query_tool.get(tool_entity).unwrap();
-                    match *tool {
-                        Tool::Move(d) => {
-                            blob.movement = d.into();
-                        }
-                        Tool::Rotate(d) => {
-                            log::info!("Rotation tool at {},{}", body.pivot.x, body.pivot.y);
-                            let mut block_iter = block_query.iter_mut().map(|(_, block)| block);
-                            match d {
-                                RotateDirection::Left => {
-                                    body.rotate_left(&mut block_iter, &mut ev_view, blob_id)
-                                }
-                                RotateDirection::Right => {
-                                    body.rotate_right(&mut block_iter, &mut ev_view, blob_id)
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
+            let rotation = match tool_at(body.pivot) {
+                Some(Tool::Move(d)) => {
+                    blob.movement = d.into();
+                    None
                 }
+                Some(Tool::Rotate(d)) if !d.is_around_tool() => Some((d, body.pivot)),
+                _ => body
+                    .blocks
+                    .iter()
+                    .filter_map(|block_id| block_query.get(*block_id).ok())
+                    .map(|(_, block)| block.position)
+                    .find_map(|pos| match tool_at(pos) {
+                        Some(Tool::Rotate(d)) if d.is_around_tool() => Some((d, pos)),
+                        _ => None,
+                    }),
+            };
+
+            if let Some((d, center)) = rotation {
+                log::info!("Rotation tool {:?} at {},{}", d, center.x, center.y);
+                let block_iter = block_query.iter_mut().map(|(_, block)| block);
+                body.rotate_around(
+                    block_iter,
+                    d.rotation(),
+                    center,
+                    field,
+                    &kicks,
+                    &mut ev_view,
+                    blob_id,
+                );
             }
         });
 }
@@ -227,17 +410,32 @@ pub fn spawn_tool(
     field_id: Entity,
     field: &Field,
     assets: &GameAssets,
+    cutter_shapes: &CutterShapes,
 ) -> Entity {
     let (px, py) = field.coords_to_px(coordinate.x, coordinate.y);
     let position = Vec3::new(px, py, Z_OVERLAY);
 
     let id = commands.spawn_empty().id();
 
+    let mut transform = Transform::from_translation(position);
     let block_children = match tool {
-        Tool::Cutter(tb) => {
-            let body_def = BodyDefinition::as_blob(gen_tetris_body(tb));
-            let mut vec =
-                Block::spawn_blocks_of_blob(commands, &body_def, coordinate, id, field_id, false);
+        Tool::Cutter(shape, orientation) => {
+            transform.rotate_z(orientation.to_radians());
+            let mut vec = if let Some(definition) = cutter_shapes.get(shape) {
+                let positions: Vec<IVec2> = definition
+                    .body
+                    .get_relative_positions()
+                    .into_iter()
+                    .map(|pos| orientation.apply(pos))
+                    .collect();
+                Block::spawn_blocks(commands, &positions, coordinate, id, field_id, false)
+            } else {
+                log::warn!(
+                    "No cutter shape {:?} in the level, only the pivot is used",
+                    shape
+                );
+                vec![]
+            };
             vec.push(id);
             vec
         }
@@ -249,11 +447,11 @@ pub fn spawn_tool(
         .insert(ToolBundle {
             sprite: SpriteBundle {
                 sprite: Sprite {
-                    color: Color::WHITE,
+                    color: assets.get_tool_tint(tool),
                     custom_size: Some(Vec2::ONE * PX_PER_TILE - 2.0),
                     ..Default::default()
                 },
-                transform: Transform::from_translation(position),
+                transform,
                 texture: assets.get_tool_image(tool).clone(),
                 ..Default::default()
             },
@@ -269,6 +467,7 @@ pub fn spawn_tool(
             group: Some(id),
             relative_position: Some(IVec2::ZERO),
             field: field_id,
+            color: BlockColor::default(),
         })
         .insert(Name::new(format!("Tool-{}", tool)));
 
@@ -277,6 +476,27 @@ pub fn spawn_tool(
     id
 }
 
+/// moves a placed tool to the coordinate, the pivot of its body and the positions of all its blocks follow
+pub fn relocate_tool(
+    body: &mut GridBody,
+    transform: &mut Transform,
+    coordinate: IVec2,
+    field: &Field,
+    block_query: &mut Query<&mut Block>,
+) {
+    let delta = coordinate - body.pivot;
+    body.pivot = coordinate;
+    for block_id in body.blocks.iter() {
+        if let Ok(mut block) = block_query.get_mut(*block_id) {
+            block.position += delta;
+        }
+    }
+
+    let (px, py) = field.coords_to_px(coordinate.x, coordinate.y);
+    transform.translation.x = px;
+    transform.translation.y = py;
+}
+
 pub fn despawn_tool(commands: &mut Commands, tool_id: Entity, query: &Query<&GridBody>) {
     if let Ok(body) = query.get(tool_id) {
         for block_id in body.blocks.iter() {
@@ -285,3 +505,45 @@ pub fn despawn_tool(commands: &mut Commands, tool_id: Entity, query: &Query<&Gri
     }
     commands.entity(tool_id).despawn_recursive();
 }
+
+#[test]
+fn test_tool_i32_roundtrip() {
+    let tools = [
+        Tool::Move(MoveDirection::Left),
+        Tool::Rotate(RotateDirection::RightAroundTool),
+        Tool::Cutter(CutterShape(0), Orientation::Deg0),
+        Tool::Cutter(CutterShape(3), Orientation::Deg270),
+        Tool::Cutter(CutterShape(10), Orientation::Deg90),
+        // the shapes do not run into the values of the other tools
+        Tool::Cutter(CutterShape(24), Orientation::Deg270),
+        Tool::Cutter(CutterShape(25), Orientation::Deg0),
+        Tool::Cutter(CutterShape(250), Orientation::Deg180),
+        Tool::Simulate,
+        Tool::Reset,
+        Tool::EraseAll,
+        Tool::Painter(BlockColor::Yellow),
+    ];
+    for tool in tools {
+        let num: i32 = tool.into();
+        assert_eq!(Tool::try_from(num), Ok(tool));
+    }
+}
+
+#[test]
+fn test_tool_descriptions() {
+    let shapes = CutterShapes::default();
+    let cutter = Tool::Cutter(CutterShape(1), Orientation::Deg90);
+    assert_eq!(cutter.variant_name(&shapes), Some("Line, 90°".to_string()));
+    assert!(cutter.description(&shapes).contains("all of them"));
+    assert_eq!(
+        Tool::Cutter(CutterShape(42), Orientation::Deg0).variant_name(&shapes),
+        Some("Shape 42, 0°".to_string())
+    );
+    assert_eq!(
+        Tool::Rotate(RotateDirection::LeftAroundTool).variant_name(&shapes),
+        Some("Left around tool".to_string())
+    );
+    assert_eq!(Tool::Eraser.variant_name(&shapes), None);
+    assert_eq!(Tool::Reset.to_string(), "Restart");
+    assert!(Tool::Reset.description(&shapes).starts_with("Restarts"));
+}
@@ -1,8 +1,10 @@
 use crate::data::prelude::*;
+#[cfg(feature = "debug")]
 use crate::input::add_tetris_control;
+use crate::input::spawn_grid_cursor;
+use crate::input::ToolActions;
 use crate::render_old::RenderableGrid;
 use crate::state::GameState;
-use crate::SECONDS_PER_ROUND;
 use crate::{field::spawn_field, prelude::*};
 use bevy::{log, prelude::*};
 
@@ -16,19 +18,46 @@ use crate::field::target::spawn_target;
 #[derive(Component, Debug, Default, PartialEq, Eq, Clone, Reflect)]
 pub struct RealBlob {}
 
+/// The blobs of the current level that wait for their spawn turn, see [`spawn_scheduled_blobs_system`]
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Reflect)]
+pub struct BlobSchedule {
+    pub pending: Vec<BlobSpawn>,
+}
+
+impl BlobSchedule {
+    /// removes and returns the pending blobs whose spawn turn has been reached
+    pub fn take_due(&mut self, turn: i32) -> Vec<BlobSpawn> {
+        let (due, pending) = self.pending.drain(..).partition(|spawn| spawn.turn <= turn);
+        self.pending = pending;
+        due
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_world(
     mut commands: Commands, // stores commands for entity/component creation / deletion
-    assets: Res<GameAssets>, // used to access files stored in the assets folder.
+    mut assets: ResMut<GameAssets>, // used to access files stored in the assets folder.
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
     mut gs: ResMut<GameState>,
     mut player_state: ResMut<PlayerStateLevel>,
     mut view_config: ResMut<ViewConfig>,
     mut evt: EventWriter<ViewUpdate>,
+    settings: Res<Settings>,
 ) {
     let level = gs.get_lvl();
     info!("Spawn world for level '{}' called.", level.num);
     player_state.set_inventory(level.applicable_tools.clone());
+    player_state.lost = false;
+    player_state.clear_tool_edits();
+
+    commands.insert_resource(GameStateLevel::new(settings.seconds_per_round));
+    commands.insert_resource(level.kick_table.clone());
+    commands.insert_resource(level.blob_collision);
 
-    commands.insert_resource(GameStateLevel::new(SECONDS_PER_ROUND));
+    let cutter_shapes = CutterShapes(level.cutter_shapes.clone());
+    assets.load_cutter_images(&cutter_shapes, &asset_server, &mut images);
+    commands.insert_resource(cutter_shapes);
 
     let factory_field_struct = Field::as_factory();
     let root_factory_field = Vec3::new(0., -70., 0.0);
@@ -42,31 +71,80 @@ pub fn spawn_world(
         "Factory Field",
         root_factory_field,
     );
+    commands.entity(fac_field_id).insert(FactoryFieldTag {});
+    spawn_grid_cursor(&mut commands, fac_field_id);
     log::info!("Factory field spawned with id: {:?}", fac_field_id);
 
-    let start_blob = spawn_blob_from_body_definition(
-        &mut commands,
-        BodyDefinition::as_blob(level.start_blob.0.clone()),
-        "Start Blob",
-        fac_field_id,
-        level.start_blob.1.into(),
-        &|ec| {
-            #[cfg(feature = "debug")]
-            add_tetris_control(ec);
+    let prod_field_id = if level.production_field {
+        let production_field_struct = Field::as_production_field();
+        let root_production_field = Vec3::new(500., -190., 0.0);
+        let (px, py) = production_field_struct.coords_to_px(0, 0);
+        view_config.tetris_topleft = Vec3::new(px, py, 0.0) + root_production_field;
 
-            ec.insert(RealBlob {});
-        },
-    );
-    evt.send(ViewUpdate::BlobSpawned(start_blob));
-    info!("Send ViewUpdate::BlobSpawned for Start Blob!");
+        let id = spawn_field(
+            &mut commands,
+            &assets,
+            production_field_struct,
+            "Production Field",
+            root_production_field,
+        );
+        commands.entity(id).insert(ProductionFieldTag {});
+        log::info!("Production field spawned with id: {:?}", id);
+        Some(id)
+    } else {
+        None
+    };
 
-    let _target_stone = spawn_target(
-        &mut commands,
-        level.target_figure.0.clone(),
-        "Target Stone",
-        Some(level.target_figure.1.into()),
-        &|_| {},
-    );
+    let mut schedule = BlobSchedule {
+        pending: level.blobs.clone(),
+    };
+    for (idx, blob_spawn) in schedule.take_due(0).into_iter().enumerate() {
+        let start_blob = spawn_blob(
+            &mut commands,
+            &blob_spawn,
+            &format!("Start Blob {}", idx),
+            fac_field_id,
+        );
+        evt.send(ViewUpdate::BlobSpawned(start_blob));
+        info!("Send ViewUpdate::BlobSpawned for Start Blob {}!", idx);
+    }
+    commands.insert_resource(schedule);
+
+    for (idx, target) in level.targets.iter().enumerate() {
+        if !target.body.iter().any(|value| {
+            matches!(
+                TargetCell::from_value(*value),
+                Some(TargetCell::Required(_))
+            )
+        }) {
+            log::warn!(
+                "Target {} has no required cell, it can never be fulfilled",
+                idx
+            );
+        }
+        let field_id = match (target.on_production_field, prod_field_id) {
+            (true, Some(prod_field_id)) => prod_field_id,
+            (true, None) => {
+                log::warn!(
+                    "Target {} needs a production field, it is placed on the factory",
+                    idx
+                );
+                fac_field_id
+            }
+            (false, _) => fac_field_id,
+        };
+        let _target_stone = spawn_target(
+            &mut commands,
+            target.body.clone(),
+            &format!("Target Stone {}", idx),
+            Some(target.position.into()),
+            target.color,
+            Some(field_id),
+            &|_| {},
+        );
+    }
+    commands.insert_resource(level.win_condition);
+    commands.insert_resource(level.constraints.clone());
     //evt.send(ViewUpdate::BlobSpawned(target_stone));
 
     spawn_text(
@@ -80,87 +158,266 @@ pub fn spawn_world(
     );
 }
 
+fn spawn_blob(
+    commands: &mut Commands,
+    blob_spawn: &BlobSpawn,
+    name: &str,
+    field: Entity,
+) -> Entity {
+    spawn_blob_from_body_definition(
+        commands,
+        BodyDefinition::as_blob(blob_spawn.body.clone()),
+        name,
+        field,
+        blob_spawn.position.into(),
+        &|ec| {
+            #[cfg(feature = "debug")]
+            add_tetris_control(ec);
+
+            ec.insert(RealBlob {});
+        },
+    )
+}
+
+/// spawns the blobs of the [`BlobSchedule`] as soon as the turn of the level reaches their spawn turn
+pub fn spawn_scheduled_blobs_system(
+    mut commands: Commands,
+    mut schedule: ResMut<BlobSchedule>,
+    query_field: Query<Entity, With<FactoryFieldTag>>,
+    level_state: Res<GameStateLevel>,
+    mut evt: EventWriter<ViewUpdate>,
+) {
+    if !level_state.is_new_turn() {
+        return;
+    }
+    let factory_id = if let Ok(factory_id) = query_field.get_single() {
+        factory_id
+    } else {
+        return;
+    };
+    //~
+
+    let turn = level_state.get_num_turn();
+    for blob_spawn in schedule.take_due(turn) {
+        let name = format!("Blob of turn {}", blob_spawn.turn);
+        let blob = spawn_blob(&mut commands, &blob_spawn, &name, factory_id);
+        evt.send(ViewUpdate::BlobSpawned(blob));
+        info!("Spawned scheduled blob in turn {}", turn);
+    }
+}
+
+/// the start position of the tetris bricks in the endless mode
+const ENDLESS_SPAWN_POSITION: (i32, i32) = (4, -3);
+
+/// spawns a random tetris brick in the endless mode whenever no blob is active anymore
 pub fn contiously_spawn_tetris_at_end(
     mut commands: Commands,
     query_active: Query<&Blob>,
-    query_field: Query<Entity, With<Field>>,
+    query_field: Query<Entity, With<FactoryFieldTag>>,
     mut level_state: ResMut<GameStateLevel>,
+    gs: Res<GameState>,
+    player_state: Res<PlayerStateLevel>,
+    mut evt: EventWriter<ViewUpdate>,
 ) {
-    if let Ok(prod_ent) = query_field.get_single() {
+    if !is_endless(&gs) || player_state.lost {
+        return;
+    }
+    //~
+
+    if let Ok(factory_id) = query_field.get_single() {
         if level_state.is_new_turn() && query_active.iter().filter(|g| g.active).count() == 0 {
-            let body = gen_random_tetris_body();
-
-            let _new_id = spawn_blob_from_body_definition(
-                &mut commands,
-                BodyDefinition::as_blob(body),
-                format!(
-                    "{}. Additional Tetris Brick",
-                    level_state.num_additional_bricks
-                )
-                .as_str(),
-                prod_ent,
-                IVec2 { x: -3, y: 3 },
-                &|ec| {
-                    add_tetris_control(ec);
-                    ec.insert(RealBlob {});
-                },
+            let blob_spawn = BlobSpawn::new(gen_random_tetris_body(), ENDLESS_SPAWN_POSITION);
+            let name = format!(
+                "{}. Additional Tetris Brick",
+                level_state.num_additional_bricks
             );
+            let new_id = spawn_blob(&mut commands, &blob_spawn, &name, factory_id);
+            evt.send(ViewUpdate::BlobSpawned(new_id));
             level_state.num_additional_bricks += 1;
-            unimplemented!("continiously spawn tetris at end needs to send events for renderer");
-            //commands.entity(prod_ent).push_children(&[new_id]);
         }
     } else {
-        panic!("The programmer forgot to create a Field and so no spawning of tetris bricks...");
+        log::warn!(
+            "The programmer forgot to create a Field and so no spawning of tetris bricks..."
+        );
     }
 }
 
+fn is_endless(gs: &GameState) -> bool {
+    gs.level.as_ref().is_some_and(|level| level.endless)
+}
+
+/// clears the full rows of dissolved blocks in levels with the line clearing rule, see [`Level::line_clearing`],
+/// and moves the dissolved blocks above them down
+pub fn clear_full_lines_system(
+    mut commands: Commands,
+    query_field: Query<(Entity, &Field)>,
+    mut query_block: Query<(Entity, &mut Block)>,
+    mut level_state: ResMut<GameStateLevel>,
+    gs: Res<GameState>,
+    mut evt: EventWriter<ViewUpdate>,
+) {
+    let line_clearing = gs.level.as_ref().is_some_and(|level| level.line_clearing);
+    if !line_clearing || !level_state.is_new_turn() {
+        return;
+    }
+    //~
+
+    for (field_id, field) in query_field.iter() {
+        let (width, height) = field.mov_size();
+        let full_rows = field
+            .get_field_state()
+            .get_full_rows(0..width as i32, 0..height as i32);
+        if full_rows.is_empty() {
+            continue;
+        }
+        //~
+
+        let mut removed = vec![];
+        let mut shifted = vec![];
+        for (entity, mut block) in query_block.iter_mut() {
+            if block.group.is_some() || block.field != field_id {
+                continue;
+            }
+            if full_rows.contains(&block.position.y) {
+                commands.entity(entity).remove::<Block>();
+                removed.push(entity);
+            } else {
+                let num_below = full_rows.iter().filter(|y| **y > block.position.y).count();
+                if num_below > 0 {
+                    block.position.y += num_below as i32;
+                    shifted.push(entity);
+                }
+            }
+        }
+
+        level_state.add_cleared_lines(full_rows.len() as u32);
+        evt.send(ViewUpdate::LineRemove(removed));
+        if !shifted.is_empty() {
+            evt.send(ViewUpdate::BlocksShifted(shifted));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn level_won_system(
     mut commands: Commands,
     assets: Res<GameAssets>,
     query_target: Query<&Target>,
-    mut query_field: Query<&mut Field>,
+    query_block: Query<&Block>,
+    query_field: Query<(Entity, &Field)>,
     mut player_state: ResMut<PlayerStateLevel>,
+    win_condition: Res<WinCondition>,
+    constraints: Res<LevelConstraints>,
+    level_state: Res<GameStateLevel>,
+    gs: Res<GameState>,
+    mut save: ResMut<SaveGame>,
+    bindings: Res<KeyBindings>,
+    schedule: Res<BlobSchedule>,
 ) {
-    if player_state.won {
+    if player_state.won || player_state.lost || query_field.is_empty() {
         return;
     }
     //~
 
-    let target = if let Ok(target) = query_target.get_single() {
-        target
-    } else {
-        return;
+    // every cell of a target has to be fulfilled on its field, e.g. filled by a block of the right color
+    let is_fulfilled = |target: &Target| {
+        query_field
+            .iter()
+            .filter(|(field_id, _)| target.is_on_field(*field_id))
+            .all(|(_, field)| {
+                target.is_fulfilled_on(field, |block_id| {
+                    query_block.get(block_id).ok().map(|block| block.color)
+                })
+            })
     };
+    let num_targets = query_target.iter().count();
+    let num_fulfilled = query_target.iter().filter(|t| is_fulfilled(t)).count();
 
-    let field = if let Ok(field) = query_field.get_single_mut() {
-        field
-    } else {
-        return;
-    };
-    //~
+    // the level isn't won before every blob has dropped in, the later blobs could still spoil the targets
+    let cond = num_targets > 0
+        && schedule.pending.is_empty()
+        && win_condition.is_met(num_fulfilled, num_targets)
+        && constraints.allows_win(level_state.num_cuts);
+    if cond {
+        player_state.won = true;
 
-    let field_state = field.get_field_state();
+        if let Some(level) = gs.level.as_ref() {
+            let stars = level.stars(level_state.get_num_turn());
+            if save.record(level.num, stars) {
+                save.save();
+            }
+        }
 
-    let coords = target.occupied_coordinates();
-    let transformed_coords = coords
-        .iter()
-        .map(|(c, r)| IVec2::new(*c, *r)) // as the factory field 12 tiles larger in y direction
-        .collect();
+        spawn_text(
+            &mut commands,
+            &assets,
+            &format!(
+                "YOU WON!!!\n\nPress <{}> to continue!\n\nAnd get a huge wall of text with your random quote as a reward!",
+                bindings.describe(ToolActions::Continue)
+            ),
+            Vec2::new(-450., -300.),
+            Vec2::new(512., 386.),
+            Color::WHITE,
+            Color::BLACK,
+        );
+    }
+}
 
-    let cond = field_state.are_all_coordinates(
-        &transformed_coords,
-        None,
-        &|el| el.kind == FieldElementKind::Block(None), // none means the blob is not existing and the blocks are direcly linked to the field
-    )
-        //&& coords.len() == field.num_occupied()
-        ;
-    if cond {
-        player_state.won = true;
+/// checks the [`LevelConstraints`] of the level, or the overflow of the field in the endless mode, and stops the simulation if one of them is violated
+#[allow(clippy::too_many_arguments)]
+pub fn level_lost_system(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    query_blob: Query<&Blob>,
+    query_field: Query<&Field>,
+    mut player_state: ResMut<PlayerStateLevel>,
+    mut level_state: ResMut<GameStateLevel>,
+    constraints: Res<LevelConstraints>,
+    schedule: Res<BlobSchedule>,
+    gs: Res<GameState>,
+    bindings: Res<KeyBindings>,
+) {
+    if player_state.won || player_state.lost {
+        return;
+    }
+    //~
+
+    let all_blobs_settled =
+        schedule.pending.is_empty() && query_blob.iter().all(|blob| !blob.active);
+    let reason = if is_endless(&gs) {
+        // the endless mode is over as soon as a dissolved block sticks out of the top of the field
+        let overflow = query_field.iter().any(|field| {
+            field
+                .get_field_state()
+                .into_iter()
+                .any(|el| el.kind == FieldElementKind::Block(None) && el.position.y < 0)
+        });
+        overflow.then(|| {
+            format!(
+                "The field is full, you cleared {} lines and scored {} points",
+                level_state.num_lines_cleared, level_state.score
+            )
+        })
+    } else {
+        constraints.violation(
+            level_state.get_num_turn(),
+            level_state.num_cuts,
+            all_blobs_settled,
+        )
+    };
+    if let Some(reason) = reason {
+        player_state.lost = true;
+        level_state.set_paused(true);
 
         spawn_text(
             &mut commands,
             &assets,
-            "YOU WON!!!\n\nPress <RETURN> to continue!\n\nAnd get a huge wall of text with your random quote as a reward!",
+            format!(
+                "YOU LOST!!!\n\n{}.\n\nPress <{}> to try again!",
+                reason,
+                bindings.describe(ToolActions::Continue)
+            )
+            .as_str(),
             Vec2::new(-450., -300.),
             Vec2::new(512., 386.),
             Color::WHITE,
@@ -168,3 +425,19 @@ pub fn level_won_system(
         );
     }
 }
+
+#[test]
+fn test_blob_schedule_take_due() {
+    let spawn = |turn| BlobSpawn::new(vec![1], (3, -4)).at_turn(turn);
+    let mut schedule = BlobSchedule {
+        pending: vec![spawn(0), spawn(5), spawn(2), spawn(5)],
+    };
+
+    assert_eq!(schedule.take_due(0), vec![spawn(0)]);
+    assert_eq!(schedule.take_due(1), vec![]);
+    // a missed turn still spawns the blob, the order of the level is kept
+    assert_eq!(schedule.take_due(3), vec![spawn(2)]);
+    assert_eq!(schedule.take_due(5), vec![spawn(5), spawn(5)]);
+    assert!(schedule.pending.is_empty());
+    assert_eq!(schedule.take_due(6), vec![]);
+}
@@ -2,18 +2,29 @@
 //! the HUD. The update_* methods keep the HUD visuals in-sync with the player state. To identify what has to be
 //! done when the HUD is clicked the UITag* components are used.
 
-use crate::{DisastrisAppState, PX_PER_ICON};
+use crate::{
+    input::{ToolActions, ToolDrag},
+    state::GameState,
+    DisastrisAppState, PX_PER_ICON,
+};
 use bevy::text::Text2dBounds;
 use bevy::{prelude::*, ui::FocusPolicy};
+use leafwing_input_manager::prelude::ActionState;
 
 use crate::field::prelude::*;
 use crate::movement::prelude::*;
 use crate::prelude::*;
 
 pub mod prelude {
+    pub use super::UITagConstraints;
+    pub use super::UITagCutCounter;
     pub use super::UITagHover;
     pub use super::UITagImage;
     pub use super::UITagInventory;
+
+    pub use super::Tooltip;
+
+    pub use super::ToolbarFocus;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Component)]
@@ -32,8 +43,49 @@ pub struct UITagInventory {
     tool_status: Tool,
 }
 
+/// Tags the text that shows how many blocks have been cut and trimmed in the current level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Component, Default)]
+pub struct UITagCutCounter {
+    num_cut: usize,
+    num_trimmed: usize,
+}
+
+/// Tags the text that shows the live counters of the level constraints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Component, Default)]
+pub struct UITagConstraints {}
+
+/// A box next to a UI element that shows a text while the element is hovered, it is spawned as a child of an
+/// element that is tagged with [`UITagHover`], see [`spawn_tooltip`] and [`tooltip_system`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Reflect, Component)]
+pub struct Tooltip {
+    pub text: String,
+}
+
+/// The button of the toolbar that is focused without a mouse, e.g. via gamepad
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub struct ToolbarFocus {
+    /// the index of the focused button, see [`toolbar_tools`]
+    pub index: Option<usize>,
+}
+
+/// gets the tools of the buttons of the toolbar from top to bottom
+pub fn toolbar_tools() -> [Tool; 8] {
+    [
+        Tool::Simulate,
+        Tool::Move(MoveDirection::default()),
+        Tool::Rotate(RotateDirection::default()),
+        Tool::Cutter(CutterShape::default(), Orientation::default()),
+        Tool::Painter(BlockColor::default()),
+        Tool::Eraser,
+        Tool::EraseAll,
+        Tool::Reset,
+    ]
+}
+
 pub fn spawn_hud(mut commands: Commands, assets: Res<GameAssets>) {
-    let ysize = PX_PER_ICON * 7.0 + 4.0 * 8.0;
+    commands.insert_resource(ToolbarFocus::default());
+
+    let ysize = PX_PER_ICON * 8.0 + 4.0 * 8.0;
 
     let pos = Vec2::new(240., 0.);
 
@@ -60,16 +112,65 @@ pub fn spawn_hud(mut commands: Commands, assets: Res<GameAssets>) {
         })
         .insert(Name::new("Toolbar"))
         .with_children(|cb| {
-            spawn_tool_button(cb, Tool::Simulate, &assets);
-            spawn_tool_button(cb, Tool::Move(MoveDirection::default()), &assets);
-            spawn_tool_button(cb, Tool::Rotate(RotateDirection::default()), &assets);
-            spawn_tool_button(cb, Tool::Cutter(TetrisBricks::default()), &assets);
-            spawn_tool_button(cb, Tool::Eraser, &assets);
-            spawn_tool_button(cb, Tool::EraseAll, &assets);
-            spawn_tool_button(cb, Tool::Reset, &assets);
+            for tool in toolbar_tools() {
+                spawn_tool_button(cb, tool, &assets);
+            }
+            spawn_cut_counter(cb, &assets);
+            spawn_constraint_counters(cb, &assets);
         });
 }
 
+fn spawn_cut_counter(cb: &mut ChildBuilder, assets: &GameAssets) {
+    let tag = UITagCutCounter::default();
+    cb.spawn(
+        TextBundle::from_section(
+            tag.to_string(),
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            // placed below the toolbar
+            position_type: PositionType::Absolute,
+            top: Val::Percent(100.0),
+            left: Val::Px(0.0),
+            ..default()
+        }),
+    )
+    .insert(Name::new("Cut Counter"))
+    .insert(tag);
+}
+
+fn spawn_constraint_counters(cb: &mut ChildBuilder, assets: &GameAssets) {
+    cb.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            // placed above the toolbar
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(100.0),
+            left: Val::Px(0.0),
+            ..default()
+        }),
+    )
+    .insert(Name::new("Constraint Counters"))
+    .insert(UITagConstraints {});
+}
+
+impl std::fmt::Display for UITagCutCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cut: {}\nTrimmed: {}", self.num_cut, self.num_trimmed)
+    }
+}
+
 fn spawn_tool_button(cb: &mut ChildBuilder, tool: Tool, assets: &GameAssets) {
     cb.spawn(ButtonBundle {
         style: Style {
@@ -125,29 +226,67 @@ fn spawn_tool_button(cb: &mut ChildBuilder, tool: Tool, assets: &GameAssets) {
                     .insert(UITagHover {
                         tool_status: tool,
                         is_hovered: false,
-                    });
+                    })
+                    .with_children(|parent| spawn_tooltip(parent, assets));
             });
     });
 }
 
+/// spawns a hidden [`Tooltip`] to the right of its parent
+pub fn spawn_tooltip(cb: &mut ChildBuilder, assets: &GameAssets) {
+    cb.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(100.0),
+            top: Val::Px(0.0),
+            width: Val::Px(320.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+        focus_policy: FocusPolicy::Pass,
+        visibility: Visibility::Hidden,
+        // the tooltip is drawn above the other buttons of the toolbar
+        z_index: ZIndex::Global(10),
+        ..default()
+    })
+    .insert(Name::new("Tooltip"))
+    .insert(Tooltip::default())
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "",
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 18.0,
+                color: Color::WHITE,
+            },
+        ));
+    });
+}
+
 pub fn toolbar_images_system(
-    mut query_images: Query<(&mut UiImage, &mut UITagImage)>,
+    mut query_images: Query<(&mut UiImage, &mut BackgroundColor, &mut UITagImage)>,
     player_state: Res<PlayerStateLevel>,
     assets: Res<GameAssets>,
 ) {
-    for (mut img, mut tag) in query_images.iter_mut() {
+    for (mut img, mut tint, mut tag) in query_images.iter_mut() {
         // Ensure tool variant in tag is the same as selected by the player
         if let Some(sel_tool) = player_state.selected_tool {
             match (sel_tool, &mut tag.tool_status) {
                 (Tool::Move(new_sel), Tool::Move(in_hud)) => *in_hud = new_sel,
                 (Tool::Rotate(new_sel), Tool::Rotate(in_hud)) => *in_hud = new_sel,
-                (Tool::Cutter(new_sel), Tool::Cutter(in_hud)) => *in_hud = new_sel,
+                (Tool::Cutter(new_shape, new_orientation), Tool::Cutter(shape, orientation)) => {
+                    *shape = new_shape;
+                    *orientation = new_orientation;
+                }
+                (Tool::Painter(new_sel), Tool::Painter(in_hud)) => *in_hud = new_sel,
                 _ => {}
             }
         }
 
         // Ensure its the right image
         *img = assets.get_tool_image(tag.tool_status).clone().into();
+        *tint = assets.get_tool_tint(tag.tool_status).into();
     }
 }
 
@@ -162,18 +301,82 @@ pub fn toolbar_inventory_system(
     }
 }
 
+pub fn cut_counter_system(
+    mut query_text: Query<(&mut Text, &mut UITagCutCounter)>,
+    mut ev_cut: EventReader<BlocksCutEvent>,
+) {
+    for ev in ev_cut.read() {
+        for (mut text, mut tag) in query_text.iter_mut() {
+            if ev.trimmed {
+                tag.num_trimmed += ev.num_blocks;
+            } else {
+                tag.num_cut += ev.num_blocks;
+            }
+            text.sections[0].value = tag.to_string();
+        }
+    }
+}
+
+pub fn constraint_counters_system(
+    mut query_text: Query<&mut Text, With<UITagConstraints>>,
+    query_tool: Query<&Tool, With<GridBody>>,
+    level_state: Res<GameStateLevel>,
+    player_state: Res<PlayerStateLevel>,
+    constraints: Res<LevelConstraints>,
+    gs: Res<GameState>,
+) {
+    let num_turn = level_state.get_num_turn();
+    let mut lines = vec![match constraints.max_turns {
+        Some(max_turns) => format!("Turn: {}/{}", num_turn, max_turns),
+        None => format!("Turn: {}", num_turn),
+    }];
+
+    let num_tools = query_tool.iter().count();
+    lines.push(match constraints.max_tools {
+        Some(max_tools) => format!("Tools: {}/{}", num_tools, max_tools),
+        None => format!("Tools: {}", num_tools),
+    });
+    lines.push(format!("Left: {}", player_state.num_tools_in_inventory()));
+
+    if constraints.require_cutter {
+        lines.push(format!("Cuts: {} (required)", level_state.num_cuts));
+    }
+
+    if let Some(level) = gs.level.as_ref() {
+        if level.line_clearing {
+            lines.push(format!("Lines: {}", level_state.num_lines_cleared));
+        }
+        if level.endless {
+            lines.push(format!("Score: {}", level_state.score));
+        }
+    }
+
+    let value = lines.join("\n");
+    for mut text in query_text.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
 pub fn toolbar_overlays_system(
     mut query_overlay: Query<(&mut BackgroundColor, &mut UITagHover)>,
     player_state: Res<PlayerStateLevel>,
+    focus: Res<ToolbarFocus>,
     assets: Res<GameAssets>,
 ) {
+    let focused_tool = focus.index.map(|index| toolbar_tools()[index]);
     for (mut hover, mut tag) in query_overlay.iter_mut() {
         // Ensure tool variant in tag is the same as selected by the player
         if let Some(sel_tool) = player_state.selected_tool {
             match (sel_tool, &mut tag.tool_status) {
                 (Tool::Move(new_sel), Tool::Move(in_hud)) => *in_hud = new_sel,
                 (Tool::Rotate(new_sel), Tool::Rotate(in_hud)) => *in_hud = new_sel,
-                (Tool::Cutter(new_sel), Tool::Cutter(in_hud)) => *in_hud = new_sel,
+                (Tool::Cutter(new_shape, new_orientation), Tool::Cutter(shape, orientation)) => {
+                    *shape = new_shape;
+                    *orientation = new_orientation;
+                }
+                (Tool::Painter(new_sel), Tool::Painter(in_hud)) => *in_hud = new_sel,
                 _ => {}
             }
         }
@@ -182,6 +385,9 @@ pub fn toolbar_overlays_system(
         let num_inv = player_state
             .num_in_inventory(tag.tool_status)
             .unwrap_or(usize::MAX);
+        // the focused button is highlighted like a hovered one
+        let is_hovered = tag.is_hovered
+            || focused_tool.is_some_and(|tool| tool == tag.tool_status.as_default_variant());
 
         // Select the overlay color
         let color: BackgroundColor = if let Some(selected_tool) = player_state.selected_tool {
@@ -193,12 +399,12 @@ pub fn toolbar_overlays_system(
                 }
             } else if num_inv == 0 {
                 assets.unavailable_button_color.into()
-            } else if tag.is_hovered {
+            } else if is_hovered {
                 assets.hover_button_color.into()
             } else {
                 assets.normal_button_color.into()
             }
-        } else if tag.is_hovered && num_inv != 0 {
+        } else if is_hovered && num_inv != 0 {
             assets.hover_button_color.into()
         } else if num_inv == 0 {
             assets.unavailable_button_color.into()
@@ -213,16 +419,20 @@ pub fn toolbar_overlays_system(
 #[allow(clippy::too_many_arguments)]
 pub fn toolbar_button_system(
     mut commands: Commands,
-    mut field_query: Query<&mut Field>,
+    mut field_query: Query<&mut Field, With<FactoryFieldTag>>,
     mut interaction_query: Query<(&Interaction, &UITagImage), Changed<Interaction>>,
     query_tool: Query<&Tool, With<GridBody>>,
     query_body: Query<&GridBody>,
     mut hover_query: Query<(&mut BackgroundColor, &mut UITagHover)>,
+    actions: Res<ActionState<ToolActions>>,
+    focus: Res<ToolbarFocus>,
     assets: Res<GameAssets>,
+    mut drag: ResMut<ToolDrag>,
     mut player_state: ResMut<PlayerStateLevel>,
     mut turn: ResMut<GameStateLevel>,
     mut next_state: ResMut<NextState<DisastrisAppState>>,
 ) {
+    let mut pressed = None;
     for (mut color, mut tag_hover) in &mut hover_query {
         for (interaction, tag) in &mut interaction_query {
             if tag_hover.tool_status != tag.tool_status {
@@ -233,26 +443,9 @@ pub fn toolbar_button_system(
             match *interaction {
                 Interaction::Pressed => {
                     *color = assets.clicked_button_color.into();
-                    match tag_hover.tool_status {
-                        Tool::Simulate => {
-                            turn.simulation_running = false;
-                        }
-                        Tool::Reset => {
-                            // From Placeholder --> PlayLevel
-                            next_state.set(DisastrisAppState::Placeholder);
-                        }
-                        Tool::EraseAll => {
-                            if let Ok(mut field) = field_query.get_single_mut() {
-                                let tools =
-                                    field.remove_all_tools(&mut commands, &query_tool, &query_body);
-                                for tool in tools {
-                                    player_state.add_to_inventory(tool, 1);
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                    player_state.selected_tool = Some(tag_hover.tool_status);
+                    pressed = Some(tag_hover.tool_status);
+                    // the tool can be dragged onto the factory field
+                    *drag = ToolDrag::FromToolbar(tag_hover.tool_status);
                 }
                 Interaction::Hovered => {
                     *color = assets.hover_button_color.into();
@@ -265,6 +458,84 @@ pub fn toolbar_button_system(
             }
         }
     }
+
+    // the focused button is pressed without a mouse, it keeps the variant that is shown in the toolbar
+    if let Some(index) = focus
+        .index
+        .filter(|_| actions.just_pressed(ToolActions::ToolbarActivate))
+    {
+        let focused_tool = toolbar_tools()[index];
+        pressed = hover_query
+            .iter()
+            .map(|(_, tag)| tag.tool_status)
+            .find(|tool| tool.as_default_variant() == focused_tool)
+            .or(Some(focused_tool));
+    }
+
+    let Some(tool) = pressed else {
+        return;
+    };
+    match tool {
+        Tool::Simulate => {
+            turn.set_paused(false);
+        }
+        Tool::Reset => {
+            // From Placeholder --> PlayLevel
+            next_state.set(DisastrisAppState::Placeholder);
+        }
+        Tool::EraseAll => {
+            if let Ok(mut field) = field_query.get_single_mut() {
+                let tools = field.remove_all_tools(&mut commands, &query_tool, &query_body);
+                let mut edits = vec![];
+                for (tool, coordinate) in tools {
+                    player_state.add_to_inventory(tool, 1);
+                    edits.push(ToolEdit::Erased { coordinate, tool });
+                }
+                player_state.push_tool_edits(edits);
+            }
+        }
+        _ => {}
+    }
+    player_state.selected_tool = Some(tool);
+}
+
+/// Moves the focus of the toolbar via the [`ToolActions`], a focused tool is selected right away while the
+/// buttons that trigger an action wait for [`ToolActions::ToolbarActivate`]
+pub fn toolbar_focus_system(
+    query_images: Query<&UITagImage>,
+    actions: Res<ActionState<ToolActions>>,
+    mut focus: ResMut<ToolbarFocus>,
+    mut player_state: ResMut<PlayerStateLevel>,
+) {
+    let tools = toolbar_tools();
+    let num = tools.len() as i32;
+    let delta = if actions.just_pressed(ToolActions::ToolbarNext) {
+        1
+    } else if actions.just_pressed(ToolActions::ToolbarPrevious) {
+        -1
+    } else {
+        return;
+    };
+    let index = match focus.index {
+        Some(index) => (index as i32 + delta).rem_euclid(num),
+        // the first focus starts at the top or the bottom of the toolbar
+        None if delta > 0 => 0,
+        None => num - 1,
+    } as usize;
+    focus.index = Some(index);
+
+    let focused_tool = tools[index];
+    let selectable = matches!(
+        focused_tool,
+        Tool::Move(_) | Tool::Rotate(_) | Tool::Cutter(_, _) | Tool::Painter(_) | Tool::Eraser
+    );
+    if selectable {
+        player_state.selected_tool = query_images
+            .iter()
+            .map(|tag| tag.tool_status)
+            .find(|tool| tool.as_default_variant() == focused_tool)
+            .or(Some(focused_tool));
+    }
 }
 
 pub fn spawn_text(
@@ -305,3 +576,62 @@ pub fn spawn_text(
             });
         });
 }
+
+/// Shows the [`Tooltip`] of the hovered UI elements and keeps their text in sync
+pub fn tooltip_system(
+    mut query_tooltip: Query<(&Tooltip, &Parent, &Children, &mut Visibility)>,
+    query_hover: Query<&UITagHover>,
+    mut query_text: Query<&mut Text>,
+) {
+    for (tooltip, parent, children, mut visibility) in query_tooltip.iter_mut() {
+        let expected = match query_hover.get(parent.get()) {
+            Ok(tag) if tag.is_hovered && !tooltip.text.is_empty() => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+        if *visibility != expected {
+            *visibility = expected;
+        }
+
+        for child in children.iter() {
+            if let Ok(mut text) = query_text.get_mut(*child) {
+                if text.sections[0].value != tooltip.text {
+                    text.sections[0].value = tooltip.text.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Describes the tools of the toolbar in their [`Tooltip`], i.e. the name, the variant, the rules, the hotkey and
+/// the number of tools that are left in the inventory
+pub fn toolbar_tooltips_system(
+    query_hover: Query<(&UITagHover, &Children)>,
+    mut query_tooltip: Query<&mut Tooltip>,
+    player_state: Res<PlayerStateLevel>,
+    bindings: Res<KeyBindings>,
+    cutter_shapes: Res<CutterShapes>,
+) {
+    for (tag, children) in query_hover.iter() {
+        let tool = tag.tool_status;
+        let mut lines = vec![match tool.variant_name(&cutter_shapes) {
+            Some(variant) => format!("{} ({})", tool, variant),
+            None => tool.to_string(),
+        }];
+        lines.push(tool.description(&cutter_shapes));
+        if let Some(action) = ToolActions::of_tool(tool) {
+            lines.push(format!("Hotkey: {}", bindings.describe(action)));
+        }
+        if let Some(num) = player_state.num_in_inventory(tool) {
+            lines.push(format!("Left: {}", num));
+        }
+        let text = lines.join("\n");
+
+        for child in children.iter() {
+            if let Ok(mut tooltip) = query_tooltip.get_mut(*child) {
+                if tooltip.text != text {
+                    tooltip.text = text.clone();
+                }
+            }
+        }
+    }
+}
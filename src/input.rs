@@ -1,22 +1,47 @@
 use crate::data::prelude::*;
-use crate::PX_PER_TILE;
+use crate::data::settings::SettingKind;
+use crate::render_old::RenderableGrid;
 use crate::{
     field::{
-        tool::{despawn_tool, spawn_tool},
+        tool::{despawn_tool, relocate_tool, spawn_tool},
         FieldRenderTag,
     },
     prelude::*,
 };
+use crate::{PX_PER_TILE, Z_OVERLAY};
 
 use crate::movement::prelude::*;
+use crate::view::prelude::*;
 
-use bevy::{
-    ecs::system::EntityCommands, input::mouse::MouseWheel, log, prelude::*, window::PrimaryWindow,
-};
+use bevy::{ecs::system::EntityCommands, log, prelude::*, window::PrimaryWindow};
 use leafwing_input_manager::prelude::*;
 
 pub struct InputMappingPlugin;
 
+/// The time in seconds a cursor action has to be held before the [`GridCursor`] moves repeatedly
+const CURSOR_REPEAT_DELAY: f32 = 0.3;
+
+/// The time in seconds between the repeated moves of the [`GridCursor`]
+const CURSOR_REPEAT_INTERVAL: f32 = 0.1;
+
+/// The cursor that selects a cell of the factory field without a mouse, i.e. via keyboard or gamepad
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub struct GridCursor {
+    /// the selected cell, it stays in the movable region of the factory field
+    pub coordinate: IVec2,
+
+    /// true if the cursor is used instead of the mouse
+    pub active: bool,
+}
+
+impl GridCursor {
+    /// snaps the coordinate to the closest cell of the movable region with the given size
+    pub fn snap(coordinate: IVec2, movable_size: (usize, usize)) -> IVec2 {
+        let max = IVec2::new(movable_size.0 as i32 - 1, movable_size.1 as i32 - 1);
+        coordinate.clamp(IVec2::ZERO, max.max(IVec2::ZERO))
+    }
+}
+
 // This is the list of "things in the game I want to be able to do based on input"
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
 pub enum WASDActions {
@@ -38,12 +63,155 @@ pub enum TetrisActionsWASD {
     RRotate,
 }
 
+/// This is the list of things the player can do with the tools in a level, the bindings are given by the
+/// [`KeyBindings`] of the player
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
+pub enum ToolActions {
+    /// places the selected tool at the cursor, the eraser removes the tool at the cursor
+    Place,
+    /// removes the tool at the cursor without selecting the eraser
+    Erase,
+    NextVariant,
+    PreviousVariant,
+    /// rotates the selected cutter counter-clock wise
+    RotateLeft,
+    /// rotates the selected cutter clock wise
+    RotateRight,
+    SelectMove,
+    SelectRotate,
+    SelectCutter,
+    SelectPainter,
+    SelectEraser,
+    /// starts or pauses the simulation
+    PlayPause,
+    /// simulates a single turn and pauses afterwards
+    Step,
+    /// reverts the last placement or removal of tools
+    Undo,
+    /// moves the [`GridCursor`] on the factory field
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    /// moves the focus of the toolbar, a focused tool is selected right away
+    ToolbarPrevious,
+    ToolbarNext,
+    /// presses the focused button of the toolbar, e.g. to start the simulation
+    ToolbarActivate,
+    /// shows or hides the predicted trajectories of the blobs
+    ToggleTrajectory,
+    /// moves on to the next level after a won level or retries a lost level
+    Continue,
+}
+
+impl ToolActions {
+    /// the key of the action in the key-binding file
+    pub fn key(&self) -> &'static str {
+        match self {
+            ToolActions::Place => "place",
+            ToolActions::Erase => "erase",
+            ToolActions::NextVariant => "next_variant",
+            ToolActions::PreviousVariant => "previous_variant",
+            ToolActions::RotateLeft => "rotate_left",
+            ToolActions::RotateRight => "rotate_right",
+            ToolActions::SelectMove => "select_move",
+            ToolActions::SelectRotate => "select_rotate",
+            ToolActions::SelectCutter => "select_cutter",
+            ToolActions::SelectPainter => "select_painter",
+            ToolActions::SelectEraser => "select_eraser",
+            ToolActions::PlayPause => "play_pause",
+            ToolActions::Step => "step",
+            ToolActions::Undo => "undo",
+            ToolActions::CursorUp => "cursor_up",
+            ToolActions::CursorDown => "cursor_down",
+            ToolActions::CursorLeft => "cursor_left",
+            ToolActions::CursorRight => "cursor_right",
+            ToolActions::ToolbarPrevious => "toolbar_previous",
+            ToolActions::ToolbarNext => "toolbar_next",
+            ToolActions::ToolbarActivate => "toolbar_activate",
+            ToolActions::ToggleTrajectory => "toggle_trajectory",
+            ToolActions::Continue => "continue",
+        }
+    }
+
+    /// the label of the action in the controls menu
+    pub fn label(&self) -> &'static str {
+        match self {
+            ToolActions::Place => "Place",
+            ToolActions::Erase => "Erase",
+            ToolActions::NextVariant => "Next variant",
+            ToolActions::PreviousVariant => "Prev. variant",
+            ToolActions::RotateLeft => "Rotate left",
+            ToolActions::RotateRight => "Rotate right",
+            ToolActions::SelectMove => "Select move",
+            ToolActions::SelectRotate => "Select rotate",
+            ToolActions::SelectCutter => "Select cutter",
+            ToolActions::SelectPainter => "Select painter",
+            ToolActions::SelectEraser => "Select eraser",
+            ToolActions::PlayPause => "Play/Pause",
+            ToolActions::Step => "Step",
+            ToolActions::Undo => "Undo",
+            ToolActions::CursorUp => "Cursor up",
+            ToolActions::CursorDown => "Cursor down",
+            ToolActions::CursorLeft => "Cursor left",
+            ToolActions::CursorRight => "Cursor right",
+            ToolActions::ToolbarPrevious => "Toolbar prev.",
+            ToolActions::ToolbarNext => "Toolbar next",
+            ToolActions::ToolbarActivate => "Toolbar press",
+            ToolActions::ToggleTrajectory => "Trajectory",
+            ToolActions::Continue => "Continue",
+        }
+    }
+
+    /// true if both actions can't share an input. The continue action is only used after a level is over, when the
+    /// other actions are not used, such that it may share its inputs with them.
+    pub fn conflicts_with(&self, other: ToolActions) -> bool {
+        *self != other && *self != ToolActions::Continue && other != ToolActions::Continue
+    }
+
+    /// the tool that is selected by the action, if any
+    pub fn tool(&self) -> Option<Tool> {
+        match self {
+            ToolActions::SelectMove => Some(Tool::Move(MoveDirection::default())),
+            ToolActions::SelectRotate => Some(Tool::Rotate(RotateDirection::default())),
+            ToolActions::SelectCutter => {
+                Some(Tool::Cutter(CutterShape::default(), Orientation::default()))
+            }
+            ToolActions::SelectPainter => Some(Tool::Painter(BlockColor::default())),
+            ToolActions::SelectEraser => Some(Tool::Eraser),
+            _ => None,
+        }
+    }
+
+    /// the action that selects or triggers the tool, e.g. to show its hotkey
+    pub fn of_tool(tool: Tool) -> Option<ToolActions> {
+        match tool {
+            Tool::Simulate => Some(ToolActions::PlayPause),
+            _ => ToolActions::variants()
+                .find(|action| action.tool() == Some(tool.as_default_variant())),
+        }
+    }
+}
+
 impl Plugin for InputMappingPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
             InputManagerPlugin::<WASDActions>::default(),
             InputManagerPlugin::<TetrisActionsWASD>::default(),
-        ));
+            InputManagerPlugin::<ToolActions>::default(),
+        ))
+        .init_resource::<ActionState<ToolActions>>()
+        .init_resource::<InputMap<ToolActions>>();
+    }
+}
+
+/// uses the changed [`KeyBindings`] as input map of the [`ToolActions`]
+pub fn apply_key_bindings_system(
+    bindings: Res<KeyBindings>,
+    mut input_map: ResMut<InputMap<ToolActions>>,
+) {
+    if bindings.is_changed() {
+        *input_map = bindings.input_map().clone();
     }
 }
 
@@ -93,61 +261,284 @@ pub fn add_arrow_control(commands: &mut EntityCommands) {
     });
 }
 
-pub fn tool_switch_via_mouse_wheel_system(
-    mut mouse_wheel_events: EventReader<MouseWheel>,
+/// Cycles through the variants of the selected tool and rotates the selected cutter before it is placed
+pub fn tool_variant_switch_system(
+    actions: Res<ActionState<ToolActions>>,
     mut player_state: ResMut<PlayerStateLevel>,
+    cutter_shapes: Res<CutterShapes>,
 ) {
-    for event in mouse_wheel_events.read() {
-        let y = event.y.signum() as i32;
-        if let Some(tool) = &mut player_state.selected_tool {
-            match tool {
-                Tool::Move(d) => {
-                    let mut cur = *d as i32;
-                    cur += y;
-                    if cur < 1 {
-                        cur = MoveDirection::max();
-                    } else if cur > MoveDirection::max() {
-                        cur = 1;
-                    }
-                    *tool = Tool::Move(cur.try_into().unwrap_or_else(|_| {
-                        panic!("Error in Enum Trait try_from({})<MoveDirection>", cur);
-                    }));
-                }
-                Tool::Rotate(d) => {
-                    let mut cur = *d as i32;
-                    cur += y;
-                    if cur < 1 {
-                        cur = RotateDirection::max();
-                    } else if cur > RotateDirection::max() {
-                        cur = 1;
-                    }
-                    *tool = Tool::Rotate(cur.try_into().unwrap_or_else(|_| {
-                        panic!("Error in Enum Trait try_from({})<RotateDirection>", cur);
-                    }));
-                }
-                Tool::Cutter(blob) => {
-                    let mut cur = *blob as i32;
-                    cur += y;
-                    cur = cur.clamp(0, TetrisBricks::max());
-                    if cur < 1 {
-                        cur = TetrisBricks::max();
-                    } else if cur > TetrisBricks::max() {
-                        cur = 1;
+    let Some(tool) = &mut player_state.selected_tool else {
+        return;
+    };
+
+    if let Tool::Cutter(_, orientation) = tool {
+        if actions.just_pressed(ToolActions::RotateLeft) {
+            *orientation = orientation.rotated(Rotation::Left);
+        } else if actions.just_pressed(ToolActions::RotateRight) {
+            *orientation = orientation.rotated(Rotation::Right);
+        }
+    }
+
+    let y = if actions.just_pressed(ToolActions::NextVariant) {
+        1
+    } else if actions.just_pressed(ToolActions::PreviousVariant) {
+        -1
+    } else {
+        return;
+    };
+    match tool {
+        Tool::Move(d) => {
+            let mut cur = *d as i32;
+            cur += y;
+            if cur < 1 {
+                cur = MoveDirection::max();
+            } else if cur > MoveDirection::max() {
+                cur = 1;
+            }
+            *tool = Tool::Move(cur.try_into().unwrap_or_else(|_| {
+                panic!("Error in Enum Trait try_from({})<MoveDirection>", cur);
+            }));
+        }
+        Tool::Rotate(d) => {
+            let mut cur = *d as i32;
+            cur += y;
+            if cur < 1 {
+                cur = RotateDirection::max();
+            } else if cur > RotateDirection::max() {
+                cur = 1;
+            }
+            *tool = Tool::Rotate(cur.try_into().unwrap_or_else(|_| {
+                panic!("Error in Enum Trait try_from({})<RotateDirection>", cur);
+            }));
+        }
+        Tool::Cutter(shape, _) => {
+            let num_shapes = cutter_shapes.len().max(1) as i32;
+            let cur = (shape.0 as i32 + y).rem_euclid(num_shapes);
+            *shape = CutterShape(cur as usize);
+        }
+        Tool::Painter(color) => {
+            let mut cur = *color as i32;
+            cur += y;
+            if cur < BlockColor::min() {
+                cur = BlockColor::max();
+            } else if cur > BlockColor::max() {
+                cur = BlockColor::min();
+            }
+            *tool = Tool::Painter(cur.try_into().unwrap_or_else(|_| {
+                panic!("Error in Enum Trait try_from({})<BlockColor>", cur);
+            }));
+        }
+        _ => {}
+    }
+}
+
+/// Selects a tool of the toolbar via its hotkey, the variant of an already selected tool is kept
+pub fn tool_selection_system(
+    actions: Res<ActionState<ToolActions>>,
+    mut player_state: ResMut<PlayerStateLevel>,
+) {
+    let Some(tool) = actions
+        .get_just_pressed()
+        .into_iter()
+        .find_map(|action| action.tool())
+    else {
+        return;
+    };
+
+    let already_selected = player_state
+        .selected_tool
+        .is_some_and(|selected| selected.as_default_variant() == tool);
+    if !already_selected {
+        player_state.selected_tool = Some(tool);
+    }
+}
+
+/// Starts, pauses or steps the simulation via the [`ToolActions`]
+pub fn simulation_control_system(
+    actions: Res<ActionState<ToolActions>>,
+    player_state: Res<PlayerStateLevel>,
+    mut level_state: ResMut<GameStateLevel>,
+) {
+    // a finished level stays paused
+    if player_state.won || player_state.lost {
+        return;
+    }
+
+    if actions.just_pressed(ToolActions::PlayPause) {
+        let paused = level_state.is_paused();
+        level_state.set_paused(!paused);
+    } else if actions.just_pressed(ToolActions::Step) {
+        level_state.step();
+    }
+}
+
+/// Shows or hides the predicted trajectories via the [`ToolActions`], the choice is stored in the [`Settings`]
+pub fn trajectory_toggle_system(
+    actions: Res<ActionState<ToolActions>>,
+    mut settings: ResMut<Settings>,
+) {
+    if actions.just_pressed(ToolActions::ToggleTrajectory) {
+        settings.cycle(SettingKind::Trajectory);
+        settings.save();
+    }
+}
+
+/// Reverts the last change of the tool layout, i.e. placed tools are removed and erased tools are placed again
+#[allow(clippy::too_many_arguments)]
+pub fn undo_tool_edit_system(
+    mut commands: Commands,
+    field_query: Query<(Entity, &Field), With<FactoryFieldTag>>,
+    query_tool: Query<&Tool>,
+    query_body: Query<&GridBody>,
+    actions: Res<ActionState<ToolActions>>,
+    assets: Res<GameAssets>,
+    cutter_shapes: Res<CutterShapes>,
+    mut player_state: ResMut<PlayerStateLevel>,
+) {
+    if !actions.just_pressed(ToolActions::Undo) {
+        return;
+    }
+    let Ok((field_id, field)) = field_query.get_single() else {
+        return;
+    };
+    let Some(edits) = player_state.pop_tool_edits() else {
+        log::info!("Nothing to undo");
+        return;
+    };
+    //~
+
+    for edit in edits.into_iter().rev() {
+        log::info!("Undo {:?}", edit);
+        match edit {
+            ToolEdit::Placed { coordinate, .. } => {
+                let tool_id = field
+                    .get_field_state()
+                    .get_element(coordinate)
+                    .and_then(|element| element.entity)
+                    .filter(|entity| query_tool.contains(*entity));
+                if let Some(tool_id) = tool_id {
+                    if let Ok(tool) = query_tool.get(tool_id) {
+                        player_state.add_to_inventory(*tool, 1);
                     }
-                    *tool = Tool::Cutter(cur.try_into().unwrap_or_else(|_| {
-                        panic!("Error in Enum Trait try_from({})<TetrisBricks>", cur);
-                    }));
+                    despawn_tool(&mut commands, tool_id, &query_body);
                 }
-                _ => {}
+            }
+            ToolEdit::Erased { coordinate, tool } => {
+                player_state.add_to_inventory(tool, -1);
+                spawn_tool(
+                    &mut commands,
+                    tool,
+                    coordinate,
+                    field_id,
+                    field,
+                    &assets,
+                    &cutter_shapes,
+                );
             }
         }
     }
 }
 
+/// spawns the [`GridCursor`] as a hidden child of the factory field
+pub fn spawn_grid_cursor(commands: &mut Commands, field_id: Entity) -> Entity {
+    let id = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.35),
+                custom_size: Some(Vec2::ONE * PX_PER_TILE),
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GridCursor::default())
+        .insert(Name::new("Grid Cursor"))
+        .id();
+    commands.entity(field_id).push_children(&[id]);
+
+    id
+}
+
+/// Moves the [`GridCursor`] via the [`ToolActions`], while it is active it gives the coordinate where tools are
+/// placed. Moving the mouse hands the placement back to the mouse.
+pub fn grid_cursor_system(
+    mut cursor_query: Query<(&mut GridCursor, &mut Transform, &mut Visibility)>,
+    field_query: Query<&Field, With<FactoryFieldTag>>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    actions: Res<ActionState<ToolActions>>,
+    time: Res<Time>,
+    mut since_move: Local<f32>,
+    mut player_state: ResMut<PlayerStateLevel>,
+) {
+    let (Ok((mut cursor, mut transform, mut visibility)), Ok(field)) =
+        (cursor_query.get_single_mut(), field_query.get_single())
+    else {
+        return;
+    };
+    //~
+
+    if cursor_moved_events.read().last().is_some() {
+        cursor.active = false;
+    }
+
+    *since_move += time.delta_seconds();
+    let repeat = *since_move >= CURSOR_REPEAT_INTERVAL;
+    let moved = |action: ToolActions| {
+        actions.just_pressed(action)
+            || (repeat
+                && actions.pressed(action)
+                && actions.current_duration(action).as_secs_f32() >= CURSOR_REPEAT_DELAY)
+    };
+    let mut delta = IVec2::ZERO;
+    if moved(ToolActions::CursorUp) {
+        delta.y -= 1;
+    }
+    if moved(ToolActions::CursorDown) {
+        delta.y += 1;
+    }
+    if moved(ToolActions::CursorLeft) {
+        delta.x -= 1;
+    }
+    if moved(ToolActions::CursorRight) {
+        delta.x += 1;
+    }
+
+    if delta != IVec2::ZERO {
+        *since_move = 0.0;
+        let movable_size = field.mov_size();
+        cursor.coordinate = if cursor.active {
+            GridCursor::snap(cursor.coordinate + delta, movable_size)
+        } else {
+            // the first move shows the cursor where the mouse has been or in the center of the movable region
+            cursor.active = true;
+            let center = IVec2::new(movable_size.0 as i32 / 2, movable_size.1 as i32 / 2);
+            GridCursor::snap(
+                player_state.tool_placement_coordinate.unwrap_or(center),
+                movable_size,
+            )
+        };
+    }
+
+    if cursor.active {
+        let (px, py) = field.coords_to_px(cursor.coordinate.x, cursor.coordinate.y);
+        transform.translation = Vec3::new(px, py, Z_OVERLAY + 1.0);
+        player_state.tool_placement_coordinate = Some(cursor.coordinate);
+    }
+    let expected = match cursor.active {
+        true => Visibility::Inherited,
+        false => Visibility::Hidden,
+    };
+    if *visibility != expected {
+        *visibility = expected;
+    }
+}
+
 pub fn grid_coordinate_via_mouse_system(
     primary_query: Query<&Window, With<PrimaryWindow>>,
     mut cursor_moved_events: EventReader<CursorMoved>,
-    mut sprites: Query<(&GlobalTransform, &Coordinate), With<FieldRenderTag>>,
+    mut sprites: Query<(&GlobalTransform, &Coordinate, &Parent), With<FieldRenderTag>>,
+    layer_query: Query<&Parent>,
+    factory_query: Query<(), With<FactoryFieldTag>>,
     mut player_state: ResMut<PlayerStateLevel>,
 ) {
     let Ok(primary) = primary_query.get_single() else {
@@ -160,7 +551,15 @@ pub fn grid_coordinate_via_mouse_system(
         let cursor_pos = moved.position - half_window;
         player_state.tool_placement_coordinate = None;
 
-        for (trans, coord) in sprites.iter_mut() {
+        for (trans, coord, layer) in sprites.iter_mut() {
+            // tools can only be placed on the factory field, the sprites are children of a layer of the field
+            let on_factory = layer_query
+                .get(layer.get())
+                .is_ok_and(|field| factory_query.contains(field.get()));
+            if !on_factory {
+                continue;
+            }
+
             let sprite_pos = trans.translation();
             let diff = Vec3::new(
                 sprite_pos.x - cursor_pos.x,
@@ -178,13 +577,165 @@ pub fn grid_coordinate_via_mouse_system(
     }
 }
 
+/// The outcome of using the selected tool at a coordinate of the factory field, see [`check_placement`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// true if the tool can be placed there, for the eraser true if there is a tool to erase
+    pub valid: bool,
+
+    /// the tool at the coordinate that is removed and refunded to the inventory, together with its entity and pivot
+    pub replaced: Option<(Entity, Tool, IVec2)>,
+}
+
+/// gets the entity of the tool at the coordinate, a cutter is found at any of its cells
+pub fn tool_entity_at(coord: IVec2, field: &Field) -> Option<Entity> {
+    match field.get_field_state().get_element(coord)?.kind {
+        FieldElementKind::Tool(entity) => Some(entity),
+        _ => None,
+    }
+}
+
+/// gets the tool at the coordinate together with its entity and pivot, see [`tool_entity_at`]
+pub fn tool_at(
+    coord: IVec2,
+    field: &Field,
+    query_tool: &Query<&Tool>,
+    query_body: &Query<&GridBody>,
+) -> Option<(Entity, Tool, IVec2)> {
+    let entity = tool_entity_at(coord, field)?;
+    let tool = query_tool.get(entity).ok()?;
+    let pivot = query_body.get(entity).map_or(coord, |body| body.pivot);
+    Some((entity, *tool, pivot))
+}
+
+/// checks if the tool can be placed at the coordinate, i.e. it is a placeable tool that is left in the inventory,
+/// every cell it covers there may hold a tool and the tool limit of the [`LevelConstraints`] is kept. The tool at the
+/// coordinate is replaced, the cells of other tools may not be covered.
+#[allow(clippy::too_many_arguments)]
+pub fn check_placement(
+    tool: Tool,
+    coord: IVec2,
+    field: &Field,
+    player_state: &PlayerStateLevel,
+    constraints: &LevelConstraints,
+    cutter_shapes: &CutterShapes,
+    query_tool: &Query<&Tool>,
+    query_body: &Query<&GridBody>,
+) -> Placement {
+    let replaced = tool_at(coord, field, query_tool, query_body);
+    if tool == Tool::Eraser {
+        return Placement {
+            valid: replaced.is_some(),
+            replaced,
+        };
+    }
+
+    let placeable_tool_selected = matches!(
+        tool,
+        Tool::Move(_) | Tool::Rotate(_) | Tool::Cutter(_, _) | Tool::Painter(_)
+    );
+    let valid_place = cells_free(
+        tool,
+        coord,
+        field,
+        cutter_shapes,
+        replaced.map(|(entity, _, _)| entity),
+    );
+    // a replaced tool makes room for the new one
+    let within_limit = replaced.is_some() || constraints.allows_tool(query_tool.iter().count());
+    Placement {
+        valid: valid_place
+            && placeable_tool_selected
+            && within_limit
+            && player_state.num_in_inventory(tool).unwrap_or(0) > 0,
+        replaced,
+    }
+}
+
+/// checks if a placed tool can be moved to the coordinate, i.e. every cell the tool covers there may hold a tool
+/// and holds no other tool, the cells that the moved tool covers itself are ignored
+pub fn check_relocation(
+    entity: Entity,
+    tool: Tool,
+    coord: IVec2,
+    field: &Field,
+    cutter_shapes: &CutterShapes,
+) -> Placement {
+    Placement {
+        valid: cells_free(tool, coord, field, cutter_shapes, Some(entity)),
+        replaced: None,
+    }
+}
+
+/// checks if every cell the tool covers at the coordinate is inside the movable region and holds no tool other
+/// than the ignored one
+fn cells_free(
+    tool: Tool,
+    coord: IVec2,
+    field: &Field,
+    cutter_shapes: &CutterShapes,
+    ignored: Option<Entity>,
+) -> bool {
+    tool.cells(coord, cutter_shapes).into_iter().all(|cell| {
+        field
+            .get_field_state()
+            .get_element(cell)
+            .is_some_and(|element| match element.kind {
+                FieldElementKind::Empty | FieldElementKind::Block(_) => true,
+                FieldElementKind::Tool(other) => Some(other) == ignored,
+                _ => false,
+            })
+    })
+}
+
+/// The drag of the place action, a tool is placed or a placed tool is moved as soon as the action is released
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum ToolDrag {
+    /// the place action has not been pressed in the level, e.g. it has been pressed to close the menu
+    #[default]
+    Idle,
+
+    /// a button of the toolbar has been pressed, the selected tool is placed where the drag ends
+    FromToolbar(Tool),
+
+    /// the place action has been pressed on the factory field, maybe on a placed tool that is dragged along
+    FromField(Option<(Entity, Tool, IVec2)>),
+}
+
+impl ToolDrag {
+    /// gets the dragged tool with its entity and pivot if it is moved by releasing the place action at the
+    /// coordinate, i.e. the drag has left the cells of the tool
+    pub fn relocated(&self, coord: IVec2, field: &Field) -> Option<(Entity, Tool, IVec2)> {
+        match *self {
+            ToolDrag::FromField(Some(dragged))
+                if tool_entity_at(coord, field) != Some(dragged.0) =>
+            {
+                Some(dragged)
+            }
+            _ => None,
+        }
+    }
+
+    /// true if releasing the place action at the coordinate is a click that places the selected tool
+    pub fn is_click(&self, coord: IVec2, field: &Field) -> bool {
+        *self != ToolDrag::Idle && self.relocated(coord, field).is_none()
+    }
+}
+
+/// Places the selected tool at the cursor or erases the tool there via the [`ToolActions`], the changes are
+/// recorded such that they can be undone. The place action acts on release such that placed tools can be dragged
+/// to another cell, see [`tool_drag_system`].
+#[allow(clippy::too_many_arguments)]
 pub fn create_tool_if_valid_clicked(
     mut commands: Commands,
-    mut field_query: Query<(Entity, &mut Field)>,
+    mut field_query: Query<(Entity, &mut Field), With<FactoryFieldTag>>,
     query_on_tool_clicked: Query<&Tool>,
     query_body: Query<&GridBody>,
-    mouse_button_input: Res<Input<MouseButton>>,
+    actions: Res<ActionState<ToolActions>>,
     assets: Res<GameAssets>,
+    cutter_shapes: Res<CutterShapes>,
+    drag: Res<ToolDrag>,
+    constraints: Res<LevelConstraints>,
     mut player_state: ResMut<PlayerStateLevel>,
 ) {
     let (field_id, field) = if let Ok(pair) = field_query.get_single_mut() {
@@ -192,51 +743,245 @@ pub fn create_tool_if_valid_clicked(
     } else {
         return;
     };
+    let Some(coord) = player_state.tool_placement_coordinate else {
+        return;
+    };
     //~
 
-    if mouse_button_input.just_pressed(MouseButton::Left) {
-        if let (Some(tool), Some(coord)) = (
-            player_state.selected_tool,
-            player_state.tool_placement_coordinate,
-        ) {
-            let placeable_tool_selected =
-                matches!(tool, Tool::Move(_) | Tool::Rotate(_) | Tool::Cutter(_));
-            let field_state = field.get_field_state();
-
-            if let Some(element) = field_state.get_element(coord) {
-                let valid_place = matches!(
-                    element.kind,
-                    FieldElementKind::Empty
-                        | FieldElementKind::Tool(_)
-                        | FieldElementKind::Block(_)
-                );
+    let place = actions.just_released(ToolActions::Place) && drag.is_click(coord, &field);
+    // the place action erases if the eraser is selected
+    let erase = actions.just_pressed(ToolActions::Erase)
+        || (place && player_state.selected_tool == Some(Tool::Eraser));
+    if !place && !erase {
+        return;
+    }
+    let mut edits = vec![];
 
-                if valid_place
-                    && placeable_tool_selected
-                    && player_state.num_in_inventory(tool).unwrap_or(0) > 0
-                {
-                    player_state.add_to_inventory(tool, -1);
-
-                    log::info!("Placed tool {:?} at ({},{})", tool, coord.x, coord.y);
-                    if let Some(entity) = element.entity {
-                        if let Ok(tool) = query_on_tool_clicked.get(entity) {
-                            player_state.add_to_inventory(*tool, 1);
-                            despawn_tool(&mut commands, entity, &query_body);
-                        }
-                    }
+    if erase {
+        let replaced = tool_at(coord, &field, &query_on_tool_clicked, &query_body);
+        if let Some((entity, tool, pivot)) = replaced {
+            log::info!("Erase tool {:?} at ({},{})", tool, pivot.x, pivot.y);
+            player_state.add_to_inventory(tool, 1);
+            despawn_tool(&mut commands, entity, &query_body);
+            edits.push(ToolEdit::Erased {
+                coordinate: pivot,
+                tool,
+            });
+        }
+    } else if let Some(tool) = player_state.selected_tool {
+        let placement = check_placement(
+            tool,
+            coord,
+            &field,
+            &player_state,
+            &constraints,
+            &cutter_shapes,
+            &query_on_tool_clicked,
+            &query_body,
+        );
 
-                    spawn_tool(&mut commands, tool, coord, field_id, &field, &assets);
-                } else if tool == Tool::Eraser {
-                    log::info!("Erase tool {:?} at ({},{})", tool, coord.x, coord.y);
+        if placement.valid {
+            player_state.add_to_inventory(tool, -1);
 
-                    if let Some(entity) = element.entity {
-                        if let Ok(tool) = query_on_tool_clicked.get(entity) {
-                            player_state.add_to_inventory(*tool, 1);
-                            despawn_tool(&mut commands, entity, &query_body);
-                        }
-                    }
-                }
+            log::info!("Placed tool {:?} at ({},{})", tool, coord.x, coord.y);
+            if let Some((entity, tool, pivot)) = placement.replaced {
+                player_state.add_to_inventory(tool, 1);
+                despawn_tool(&mut commands, entity, &query_body);
+                edits.push(ToolEdit::Erased {
+                    coordinate: pivot,
+                    tool,
+                });
             }
+
+            spawn_tool(
+                &mut commands,
+                tool,
+                coord,
+                field_id,
+                &field,
+                &assets,
+                &cutter_shapes,
+            );
+            edits.push(ToolEdit::Placed {
+                coordinate: coord,
+                tool,
+            });
         }
     }
+
+    player_state.push_tool_edits(edits);
+}
+
+/// Drags placed tools with the place action, a tool that is dropped on another cell is moved there without changing
+/// the inventory. The move is recorded such that it can be undone.
+#[allow(clippy::too_many_arguments)]
+pub fn tool_drag_system(
+    field_query: Query<&Field, With<FactoryFieldTag>>,
+    query_tool: Query<&Tool>,
+    mut query_body: Query<(&mut GridBody, &mut Transform), With<Tool>>,
+    mut query_block: Query<&mut Block>,
+    actions: Res<ActionState<ToolActions>>,
+    mut drag: ResMut<ToolDrag>,
+    mut player_state: ResMut<PlayerStateLevel>,
+    cutter_shapes: Res<CutterShapes>,
+) {
+    let Ok(field) = field_query.get_single() else {
+        return;
+    };
+    let coord = player_state.tool_placement_coordinate;
+    //~
+
+    if actions.just_pressed(ToolActions::Place) {
+        // a press on the toolbar has already been recorded by the toolbar
+        if let Some(coord) = coord {
+            let dragged = tool_entity_at(coord, field).and_then(|entity| {
+                let tool = query_tool.get(entity).ok()?;
+                let (body, _) = query_body.get(entity).ok()?;
+                Some((entity, *tool, body.pivot))
+            });
+            *drag = ToolDrag::FromField(dragged);
+        }
+    } else if actions.just_released(ToolActions::Place) {
+        let released = std::mem::take(&mut *drag);
+        let Some(coord) = coord else {
+            return;
+        };
+        let Some((entity, tool, pivot)) = released.relocated(coord, field) else {
+            return;
+        };
+        if !check_relocation(entity, tool, coord, field, &cutter_shapes).valid {
+            return;
+        }
+        //~
+
+        if let Ok((mut body, mut transform)) = query_body.get_mut(entity) {
+            log::info!(
+                "Move tool {:?} from ({},{}) to ({},{})",
+                tool,
+                pivot.x,
+                pivot.y,
+                coord.x,
+                coord.y
+            );
+            relocate_tool(&mut body, &mut transform, coord, field, &mut query_block);
+            player_state.push_tool_edits(vec![
+                ToolEdit::Erased {
+                    coordinate: pivot,
+                    tool,
+                },
+                ToolEdit::Placed {
+                    coordinate: coord,
+                    tool,
+                },
+            ]);
+        }
+    }
+}
+
+#[test]
+fn test_grid_cursor_snap() {
+    assert_eq!(
+        GridCursor::snap(IVec2::new(3, 4), (10, 24)),
+        IVec2::new(3, 4)
+    );
+    // the overlap of the factory field is outside of the movable region
+    assert_eq!(GridCursor::snap(IVec2::new(-2, -4), (10, 24)), IVec2::ZERO);
+    assert_eq!(
+        GridCursor::snap(IVec2::new(12, 30), (10, 24)),
+        IVec2::new(9, 23)
+    );
+    assert_eq!(GridCursor::snap(IVec2::new(1, 1), (0, 0)), IVec2::ZERO);
+}
+
+#[test]
+fn test_placement_covers_all_cells() {
+    use bevy::ecs::system::SystemState;
+
+    let shapes = CutterShapes::default();
+    let cutter = Tool::Cutter(CutterShape(1), Orientation::Deg0);
+    let mover = Tool::Move(MoveDirection::Left);
+    let mut world = World::new();
+    let dragged = world.spawn((cutter, GridBody::new(IVec2::new(4, 6)))).id();
+    let other = world.spawn((mover, GridBody::new(IVec2::new(4, 12)))).id();
+
+    let mut field = Field::as_factory();
+    let mut state = FieldState::new(field.bounds());
+    let (min, max) = field.bounds();
+    for x in min.x..max.x {
+        for y in min.y..max.y {
+            if x < 0 || y < 0 || x >= field.mov_size().0 as i32 || y >= field.mov_size().1 as i32 {
+                let mut element = state.get_element(IVec2::new(x, y)).unwrap();
+                element.kind = FieldElementKind::OutOfMovableRegion;
+                state.set_element(element.position, element);
+            }
+        }
+    }
+    let mut place = |tool: Tool, pivot: IVec2, entity: Entity| {
+        for cell in tool.cells(pivot, &shapes) {
+            let mut element = state.get_element(cell).unwrap();
+            element.kind = FieldElementKind::Tool(entity);
+            state.set_element(cell, element);
+        }
+    };
+    place(cutter, IVec2::new(4, 6), dragged);
+    place(mover, IVec2::new(4, 12), other);
+    field.set_field_state(state);
+
+    let mut player_state = PlayerStateLevel::default();
+    player_state.set_inventory(
+        [
+            (cutter.as_default_variant(), 1),
+            (mover.as_default_variant(), 1),
+        ]
+        .into(),
+    );
+    let constraints = LevelConstraints::default();
+    let mut system_state: SystemState<(Query<&Tool>, Query<&GridBody>)> =
+        SystemState::new(&mut world);
+    let (query_tool, query_body) = system_state.get(&world);
+    let placement = |tool: Tool, coord: IVec2| {
+        check_placement(
+            tool,
+            coord,
+            &field,
+            &player_state,
+            &constraints,
+            &shapes,
+            &query_tool,
+            &query_body,
+        )
+    };
+    let relocated = |coord: IVec2| check_relocation(dragged, cutter, coord, &field, &shapes).valid;
+
+    let cells = cutter.cells(IVec2::ZERO, &shapes);
+    assert!(cells.len() > 1);
+
+    // the cells of the dragged cutter itself do not block the drop
+    assert!(relocated(IVec2::new(4, 7)));
+    assert!(relocated(IVec2::new(5, 6)));
+    // the tool at the pivot is replaced by a placed cutter
+    let replacing = placement(cutter, IVec2::new(4, 12));
+    assert!(replacing.valid);
+    assert_eq!(replacing.replaced, Some((other, mover, IVec2::new(4, 12))));
+    // the pivot is free but another cell lands on the other tool
+    for offset in cells.iter().skip(1) {
+        let coord = IVec2::new(4, 12) - *offset;
+        assert_eq!(
+            field.get_field_state().get_element(coord).unwrap().kind,
+            FieldElementKind::Empty
+        );
+        assert!(!relocated(coord));
+        assert!(!placement(cutter, coord).valid);
+        assert!(placement(mover, coord).valid);
+    }
+    // the pivot is inside the movable region but another cell is not
+    let offset = cells[1];
+    let edge = |offset: i32, min: i32, max: i32, center: i32| match offset.signum() {
+        -1 => min,
+        1 => max,
+        _ => center,
+    };
+    let coord = IVec2::new(edge(offset.x, 0, 9, 4), edge(offset.y, 0, 23, 6));
+    assert!(!relocated(coord));
+    assert!(!placement(cutter, coord).valid);
 }
@@ -22,6 +22,7 @@ use bevy::prelude::*;
 use bevy::window::{PresentMode, PrimaryWindow};
 use bevy::DefaultPlugins;
 use bevy_tweening::TweeningPlugin;
+use leafwing_input_manager::plugin::InputManagerSystem;
 
 use rand::Rng;
 
@@ -35,6 +36,8 @@ use crate::{
     field::target::{Coordinate, Target},
     field::Block,
     field::Field,
+    hud::UITagConstraints,
+    hud::UITagCutCounter,
     hud::UITagHover,
     hud::UITagImage,
     hud::UITagInventory,
@@ -49,7 +52,9 @@ pub mod field;
 pub mod game;
 pub mod hud;
 pub mod input;
+pub mod menu;
 pub mod movement;
+pub mod preview;
 pub mod render_old;
 pub mod state;
 pub mod view;
@@ -67,7 +72,11 @@ pub const TUTORIAL: &str = "Disassemble the useless input blob that was delivere
 
 pub const TUT1: &str = "Hello disastros engineer, your task is to move the gray BLOB such that it hits the light red target area. On the right toolbar you see multiple tools - You have one ROTATOR, place it wisely somewhere in the building area (dark read). Play starts the simlation.";
 pub const TUT2: &str = "Well done disastros engineer, your second tasks involves multiple tools. After you selected a tool on the right toolbar you can change its variant via the mouse wheel. Place all tools to the blue building area in a way that the gray BLOB moves into the red target area.";
-pub const TUT3: &str = "Yass, lets get disastros and let us apply the CUTTER tool! There are many variants, remember the mouse wheel to select them. Place the cutter and other tools on the blue building area. Do you have what it needs to fill up the red target area?";
+pub const TUT3: &str = "Yass, lets get disastros and let us apply the CUTTER tool! There are many variants, remember the mouse wheel to select them and SHIFT + mouse wheel or Q/E to rotate them. Place the cutter and other tools on the blue building area. Do you have what it needs to fill up the red target area?";
+pub const TUT4: &str = "Careful disastros engineer, the delivery keeps coming! A second gray BLOB drops in at turn 10. Use the MOVE tools, remember the mouse wheel to change their direction, such that both BLOBs end up side by side in the red target area.";
+pub const TUT5: &str = "Time for some color, disastros engineer! The red target area only accepts RED blocks. Select the PAINTER, choose its color via the mouse wheel and place it such that every block of the gray BLOB passes over it on the way to the target.";
+
+pub const TUT_ENDLESS: &str = "Welcome to the endless shift, disastros engineer! Tetris bricks keep arriving, build a factory that shapes them into full rows at the bottom. Full rows are cleared and raise your score, but once the stack reaches over the top your shift is over.";
 
 pub fn get_random_quote() -> String {
     let v = [QUOTE1, QUOTE2, QUOTE3];
@@ -94,7 +103,10 @@ pub mod constants {
     pub use super::TUT1;
     pub use super::TUT2;
     pub use super::TUT3;
+    pub use super::TUT4;
+    pub use super::TUT5;
     pub use super::TUTORIAL;
+    pub use super::TUT_ENDLESS;
 
     pub use super::PX_PER_ICON;
     pub use super::PX_PER_TILE;
@@ -164,11 +176,53 @@ impl FromStr for DisastrisAppState {
 pub struct GameConfig {
     pub start_level: u32,
 
+    /// the file the progress of the player is stored in, see [`data::save::SaveGame`]
+    pub save_file: Option<std::path::PathBuf>,
+
+    /// the options of the game as loaded from the config file and overridden by the command line
+    pub settings: data::settings::Settings,
+
+    /// the file the controls of the player are stored in, see [`data::bindings::KeyBindings`]
+    pub bindings_file: Option<std::path::PathBuf>,
+
     pub start_state: String,
 
     pub state_from_placeholder: DisastrisAppState,
 }
 
+fn present_mode(vsync: bool) -> PresentMode {
+    if vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    }
+}
+
+/// applies the changed [`data::settings::Settings`] to the window, the user interface and the running level
+pub fn apply_settings_system(
+    settings: Res<data::settings::Settings>,
+    mut primary_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+    mut view_config: ResMut<view::ViewConfig>,
+    mut level_state: ResMut<state::GameStateLevel>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    //~
+
+    if let Ok(mut window) = primary_query.get_single_mut() {
+        let (width, height) = settings.resolution;
+        if window.resolution.width() != width || window.resolution.height() != height {
+            window.resolution.set(width, height);
+        }
+        window.present_mode = present_mode(settings.vsync);
+    }
+    ui_scale.0 = settings.ui_scale;
+    view_config.anim_duration = Duration::from_millis(settings.anim_duration_ms);
+    level_state.set_time_per_turn(settings.seconds_per_round);
+}
+
 pub fn placeholder_on_enter_into_next(
     mut next_state: ResMut<NextState<DisastrisAppState>>,
     game_config: Res<GameConfig>,
@@ -180,15 +234,22 @@ pub fn placeholder_on_enter_into_next(
 pub fn start_disastris(config: GameConfig) {
     let mut app = App::new();
 
-    app.insert_resource(config)
+    let settings = config.settings.clone();
+    app.insert_resource(data::save::SaveGame::load(config.save_file.clone()))
+        .insert_resource(data::bindings::KeyBindings::load(
+            config.bindings_file.clone(),
+        ))
+        .insert_resource(UiScale(settings.ui_scale))
+        .insert_resource(settings.clone())
+        .insert_resource(config)
         .insert_resource(AssetMetaCheck::Never);
 
     app.add_plugins(DefaultPlugins.set(WindowPlugin {
         primary_window: Some(Window {
             title: "Disastris - A game that only sees small progress after Bevy Releases or Game Jams.".into(),
             position: WindowPosition::Centered(MonitorSelection::Primary),
-            resolution: (1400., 1000.).into(),
-            present_mode: PresentMode::AutoVsync,
+            resolution: settings.resolution.into(),
+            present_mode: present_mode(settings.vsync),
             // Tells wasm to resize the window according to the available canvas
             fit_canvas_to_parent: true,
             // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
@@ -200,15 +261,34 @@ pub fn start_disastris(config: GameConfig) {
     .add_plugins((input::InputMappingPlugin, TweeningPlugin));
 
     app.add_event::<movement::BlobMoveEvent>()
-        .add_event::<view::ViewUpdate>();
+        .add_event::<view::ViewUpdate>()
+        .add_event::<field::tool::BlocksCutEvent>()
+        .init_resource::<movement::KickTable>()
+        .init_resource::<movement::BlobCollision>()
+        .init_resource::<data::bodies::CutterShapes>()
+        .init_resource::<game::BlobSchedule>()
+        .init_resource::<field::target::WinCondition>()
+        .init_resource::<data::level::LevelConstraints>()
+        .init_resource::<menu::MenuState>()
+        .init_resource::<menu::RebindState>()
+        .init_resource::<hud::ToolbarFocus>()
+        .init_resource::<input::ToolDrag>();
 
     app.add_state::<DisastrisAppState>();
 
     // initial initializiation during startup
     app.add_systems(Startup, initial_start_setup);
+    app.add_systems(
+        Update,
+        (apply_settings_system, input::apply_key_bindings_system),
+    );
     app.add_systems(
         OnEnter(DisastrisAppState::PlayLevel),
-        (game::spawn_world, hud::spawn_hud),
+        (
+            game::spawn_world,
+            hud::spawn_hud,
+            menu::enter_level_close_menu,
+        ),
     );
 
     app.add_systems(
@@ -223,7 +303,21 @@ pub fn start_disastris(config: GameConfig) {
     );
 
     // @TODO initialization and cleanup for other states
-    app.add_systems(OnEnter(DisastrisAppState::Mainmenu), hud::spawn_hud);
+    app.add_systems(OnEnter(DisastrisAppState::Mainmenu), menu::enter_main_menu);
+    app.add_systems(
+        Update,
+        (
+            menu::menu_navigation_system,
+            menu::menu_rebinding_system,
+            menu::menu_page_system,
+            menu::menu_highlight_system,
+        )
+            .chain()
+            .run_if(
+                in_state(DisastrisAppState::Mainmenu)
+                    .or_else(in_state(DisastrisAppState::PlayLevel)),
+            ),
+    );
     app.add_systems(
         OnExit(DisastrisAppState::Mainmenu),
         state::clean_all_state_entities,
@@ -256,20 +350,40 @@ pub fn start_disastris(config: GameConfig) {
                 field::tool::apply_movement_tools,
                 field::field_states_generation_system,
                 field::tool::apply_cutter_tool,
+                field::tool::apply_painter_tools,
+                field::field_states_generation_system,
+                game::clear_full_lines_system,
                 field::field_states_generation_system,
             )
                 .chain(),
-            state::app_state_transition_system,
+            // the transition reads the continue action of this frame
+            state::app_state_transition_system.after(InputManagerSystem::Update),
         ),
     );
 
     app.add_systems(
         Update,
         (
-            hud::toolbar_button_system,
-            input::tool_switch_via_mouse_wheel_system,
-            input::grid_coordinate_via_mouse_system,
+            // the tools cannot be selected or placed while the pause menu is open
+            (
+                hud::toolbar_focus_system,
+                hud::toolbar_button_system,
+                input::tool_variant_switch_system,
+                input::tool_selection_system,
+                input::simulation_control_system,
+                input::trajectory_toggle_system,
+                input::undo_tool_edit_system,
+                (
+                    input::grid_coordinate_via_mouse_system,
+                    input::grid_cursor_system,
+                    preview::tool_ghost_system,
+                )
+                    .chain(),
+            )
+                .run_if(menu::menu_closed),
             movement::move_events_by_gravity_system,
+            game::spawn_scheduled_blobs_system,
+            game::contiously_spawn_tetris_at_end,
             field::blob::move_blob_by_input,
         ),
     );
@@ -278,7 +392,9 @@ pub fn start_disastris(config: GameConfig) {
         PostUpdate,
         (
             movement::handle_move_blob_events,
-            input::create_tool_if_valid_clicked,
+            (input::create_tool_if_valid_clicked, input::tool_drag_system)
+                .chain()
+                .run_if(menu::menu_closed),
         ),
     );
 
@@ -290,9 +406,14 @@ pub fn start_disastris(config: GameConfig) {
                 view::animate_rendered_blob_system,
             )
                 .chain(),
+            view::despawn_removed_blocks_system,
             hud::toolbar_images_system,
             hud::toolbar_inventory_system,
             hud::toolbar_overlays_system,
+            (hud::toolbar_tooltips_system, hud::tooltip_system).chain(),
+            preview::trajectory_overlay_system,
+            hud::cut_counter_system,
+            hud::constraint_counters_system,
             render_old::old_render_entities_system::<field::Field>, // still needed to render target blob @todo get rid of it
             render_old::show_block_with_debug_tag_system,
         ),
@@ -304,6 +425,7 @@ pub fn start_disastris(config: GameConfig) {
         (
             field::field_states_generation_system,
             game::level_won_system,
+            game::level_lost_system,
         )
             .chain(),
     );
@@ -316,15 +438,37 @@ pub fn start_disastris(config: GameConfig) {
         .register_type::<Block>()
         .register_type::<Target>()
         .register_type::<Field>()
+        .register_type::<field::FactoryFieldTag>()
+        .register_type::<field::ProductionFieldTag>()
+        .register_type::<UITagConstraints>()
+        .register_type::<UITagCutCounter>()
         .register_type::<UITagImage>()
         .register_type::<UITagHover>()
         .register_type::<UITagInventory>()
+        .register_type::<hud::Tooltip>()
+        .register_type::<hud::ToolbarFocus>()
+        .register_type::<input::GridCursor>()
+        .register_type::<input::ToolDrag>()
+        .register_type::<preview::ToolGhost>()
+        .register_type::<preview::TrajectoryOverlay>()
         .register_type::<Interaction>()
         .register_type::<Level>()
         .register_type::<GameStateLevel>()
         .register_type::<game::RealBlob>()
+        .register_type::<game::BlobSchedule>()
+        .register_type::<data::save::SaveGame>()
+        .register_type::<menu::MenuState>()
+        .register_type::<menu::RebindState>()
+        .register_type::<data::settings::Settings>()
+        .register_type::<data::bindings::KeyBindings>()
+        .register_type::<menu::UITagMenu>()
+        .register_type::<menu::UITagMenuButton>()
+        .register_type::<field::target::WinCondition>()
+        .register_type::<data::level::LevelConstraints>()
         .register_type::<PlayerStateLevel>()
         .register_type::<GameState>()
+        .register_type::<crate::movement::KickTable>()
+        .register_type::<crate::movement::BlobCollision>()
         .register_type::<OverStatePersistenceTag>()
         .register_type::<GridBody>();
 
@@ -361,7 +505,9 @@ fn initial_start_setup(
         upcoming_level: Some(data::level::Level::new(config.start_level)),
     };
     commands.insert_resource(gs);
-    commands.insert_resource(state::GameStateLevel::new(SECONDS_PER_ROUND));
+    commands.insert_resource(state::GameStateLevel::new(
+        config.settings.seconds_per_round,
+    ));
 
     let assets = data::assets::GameAssets::new(&asset_server);
     let id = view::spawn_simple_rendering_entity(&mut commands).id();
@@ -369,7 +515,7 @@ fn initial_start_setup(
         renderer_entity: id,
         factory_topleft: Vec3::ZERO,
         tetris_topleft: Vec3::ZERO,
-        anim_duration: Duration::from_millis(200),
+        anim_duration: Duration::from_millis(config.settings.anim_duration_ms),
         brick_image: assets.block_blob.clone(),
         test_blob: None,
     });
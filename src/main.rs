@@ -6,14 +6,14 @@
 //! ## TODOs
 //! - [x] Choose level
 //! - [ ] Use folders
-//! - [ ] Startup in different game states, e.g. mainmenu vs. ingame, etc.
+//! - [x] Startup in different game states, e.g. mainmenu vs. ingame, etc.
 //!
 //! Most of its functionality and therefore the documentation relies in the [`::bevy_jam_2_disastris_lib`].
 
 use std::{env, path::PathBuf};
 
 use clap::{Parser, Subcommand};
-use disastris_lib::{start_disastris, GameConfig};
+use disastris_lib::{data::prelude::Settings, start_disastris, GameConfig};
 
 #[derive(Subcommand, Clone, Copy, Default)]
 pub enum CliCommands {
@@ -28,14 +28,30 @@ pub enum CliCommands {
 #[derive(Parser)]
 #[command(author="Gereon Bartel, Tim Janus and Philipp Sieweck", version="0.2.0", about="A game for the bevy-jam2", long_about=None)]
 struct Cli {
-    /// the number of the level that should be loaded at game startup
+    /// the number of the level that should be loaded at game startup, 0 starts the endless mode
     #[arg(short, long, value_name = "LVL_NO", default_value_t = 1)]
     level: u32,
 
     /// The state in that Disastris shall shart, supported are 'Mainmenu', 'PlayLevel' and 'AnimationTest'.
-    #[arg(short, long, value_name = "START_STATE", default_value = "PlayLevel")]
+    #[arg(short, long, value_name = "START_STATE", default_value = "Mainmenu")]
     start_state: String,
 
+    #[arg(long, value_name = "SAVE_FILE", default_value = "disastris.save")]
+    /// path to the file that stores the progress of the player, relative paths start at the working directory
+    save_file: PathBuf,
+
+    #[arg(long, value_name = "CONFIG_FILE", default_value = "disastris.cfg")]
+    /// path to the file that stores the settings, relative paths start at the working directory
+    config_file: PathBuf,
+
+    #[arg(long, value_name = "BINDINGS_FILE", default_value = "disastris.keys")]
+    /// path to the file that stores the key bindings, relative paths start at the working directory
+    bindings_file: PathBuf,
+
+    #[arg(long = "setting", value_name = "KEY=VALUE")]
+    /// overrides a setting of the config file, e.g. `--setting resolution=1920x1200`, may be given several times
+    settings: Vec<String>,
+
     #[arg(short, long, value_name = "RES_FOLDER")]
     /// path to the folder containing the resources / assets of the game
     resource_folder: Option<PathBuf>,
@@ -75,8 +91,33 @@ impl Cli {
             Err(_) => PathBuf::from("."),
         };
 
+        let save_file = match env::current_dir() {
+            Ok(cwd) if self.save_file.is_relative() => cwd.join(self.save_file),
+            _ => self.save_file,
+        };
+
+        let config_file = match env::current_dir() {
+            Ok(cwd) if self.config_file.is_relative() => cwd.join(self.config_file),
+            _ => self.config_file,
+        };
+
+        let bindings_file = match env::current_dir() {
+            Ok(cwd) if self.bindings_file.is_relative() => cwd.join(self.bindings_file),
+            _ => self.bindings_file,
+        };
+
+        let mut settings = Settings::load(Some(config_file));
+        for pair in &self.settings {
+            if let Err(err) = settings.apply_override(pair) {
+                eprintln!("Ignoring setting '{}': {}", pair, err);
+            }
+        }
+
         CliParameters {
             level_num: self.level,
+            save_file,
+            settings,
+            bindings_file,
             output_folder: abs_out_folder,
             start_state: self.start_state,
             subcommand,
@@ -87,6 +128,12 @@ impl Cli {
 struct CliParameters {
     level_num: u32,
 
+    save_file: PathBuf,
+
+    settings: Settings,
+
+    bindings_file: PathBuf,
+
     start_state: String,
 
     output_folder: PathBuf,
@@ -103,6 +150,9 @@ fn main() {
         CliCommands::Game => {
             let config = GameConfig {
                 start_level: cli.level_num,
+                save_file: Some(cli.save_file),
+                settings: cli.settings,
+                bindings_file: Some(cli.bindings_file),
                 start_state: cli.start_state,
                 state_from_placeholder: disastris_lib::DisastrisAppState::PlayLevel,
             };
@@ -0,0 +1,687 @@
+//! The menu module contains the main menu of the game, its level select, settings and controls pages and the pause
+//! menu that is opened by <ESC> while a level is played.
+//!
+//! The menu can be navigated by mouse, keyboard (arrow keys or WASD, <RETURN> to activate and <ESC> to go back)
+//! and gamepad (DPad, South to activate and East to go back).
+
+use bevy::{app::AppExit, input::mouse::MouseWheel, prelude::*, ui::FocusPolicy};
+use leafwing_input_manager::{
+    prelude::*,
+    user_input::{InputKind, Modifier},
+};
+
+use crate::{
+    data::{bindings::format_input, prelude::*, save::MAX_STARS, settings::SettingKind},
+    input::ToolActions,
+    state::GameState,
+    DisastrisAppState,
+};
+
+/// The number of columns of the level grid on the level select page
+const LEVEL_SELECT_COLUMNS: usize = 4;
+
+/// The number of columns of the actions on the controls page
+const CONTROLS_COLUMNS: usize = 4;
+
+/// The pages of the main menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
+pub enum MenuPage {
+    #[default]
+    Main,
+
+    LevelSelect,
+
+    /// the overlay that is opened while a level is played
+    Pause,
+
+    Settings,
+
+    /// the bindings of the [`ToolActions`], reached from the settings page
+    Controls,
+}
+
+/// The actions that are triggered by the buttons of the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum MenuAction {
+    /// starts the first level that has not been solved yet
+    Continue,
+    LevelSelect,
+    Editor,
+    Settings,
+    Quit,
+    PlayLevel(u32),
+    Back,
+    Resume,
+    RestartLevel,
+    MainMenu,
+
+    /// switches the option to its next value
+    CycleSetting(SettingKind),
+
+    Controls,
+
+    /// waits for the next input of the player and binds it to the action
+    Rebind(ToolActions),
+
+    /// restores the default bindings
+    ResetBindings,
+}
+
+/// The currently shown page and the selected button on it
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub struct MenuState {
+    pub page: MenuPage,
+
+    /// the index of the selected button on the page
+    pub selected: usize,
+
+    /// true if the menu is shown, in the level the game is paused while the menu is open
+    pub open: bool,
+}
+
+impl MenuState {
+    fn show(page: MenuPage, selected: usize) -> Self {
+        MenuState {
+            page,
+            selected,
+            open: true,
+        }
+    }
+}
+
+/// The action of the controls page that waits for an input of the player
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Reflect)]
+pub struct RebindState {
+    pub action: Option<ToolActions>,
+
+    /// the last input that could not be bound because it is bound to another action
+    pub conflict: Option<(String, ToolActions)>,
+}
+
+/// run condition for the systems that handle the input of the player in a level
+pub fn menu_closed(menu_state: Res<MenuState>) -> bool {
+    !menu_state.open
+}
+
+/// Tags the root node of the menu with the page it shows
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct UITagMenu {
+    page: MenuPage,
+}
+
+/// Tags a button of the menu
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct UITagMenuButton {
+    action: MenuAction,
+
+    /// the index of the button on its page, used for keyboard and gamepad navigation
+    index: usize,
+
+    /// disabled buttons are shown but cannot be activated
+    enabled: bool,
+}
+
+/// A button of a menu page
+struct MenuEntry {
+    action: MenuAction,
+    label: String,
+    enabled: bool,
+}
+
+impl MenuEntry {
+    fn new(action: MenuAction, label: impl Into<String>) -> Self {
+        MenuEntry {
+            action,
+            label: label.into(),
+            enabled: true,
+        }
+    }
+
+    fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+impl MenuPage {
+    /// the number of columns the buttons of the page are arranged in
+    fn columns(&self) -> usize {
+        match self {
+            MenuPage::Main | MenuPage::Pause | MenuPage::Settings => 1,
+            MenuPage::LevelSelect => LEVEL_SELECT_COLUMNS,
+            MenuPage::Controls => CONTROLS_COLUMNS,
+        }
+    }
+
+    fn entries(
+        &self,
+        save: &SaveGame,
+        settings: &Settings,
+        bindings: &KeyBindings,
+        rebind: &RebindState,
+    ) -> Vec<MenuEntry> {
+        match self {
+            MenuPage::Main => vec![
+                MenuEntry::new(
+                    MenuAction::Continue,
+                    format!("Continue (Level {})", save.next_unsolved(Level::NUM_LEVELS)),
+                ),
+                MenuEntry::new(MenuAction::LevelSelect, "Level Select"),
+                MenuEntry::new(MenuAction::Editor, "Editor").disabled(),
+                MenuEntry::new(MenuAction::Settings, "Settings"),
+                MenuEntry::new(MenuAction::Quit, "Quit"),
+            ],
+            MenuPage::LevelSelect => {
+                let mut entries: Vec<MenuEntry> = (1..=Level::NUM_LEVELS)
+                    .map(|num| {
+                        let status = match save.stars(num) {
+                            Some(stars) => format!("Solved {}/{} stars", stars, MAX_STARS),
+                            None => "Not solved".to_string(),
+                        };
+                        MenuEntry::new(
+                            MenuAction::PlayLevel(num),
+                            format!("Level {}\n{}", num, status),
+                        )
+                    })
+                    .collect();
+                entries.push(MenuEntry::new(
+                    MenuAction::PlayLevel(Level::ENDLESS),
+                    "Endless\nMode",
+                ));
+                entries.push(MenuEntry::new(MenuAction::Back, "Back"));
+                entries
+            }
+            MenuPage::Pause => vec![
+                MenuEntry::new(MenuAction::Resume, "Resume"),
+                MenuEntry::new(MenuAction::RestartLevel, "Restart Level"),
+                MenuEntry::new(MenuAction::LevelSelect, "Level Select"),
+                MenuEntry::new(MenuAction::Settings, "Settings"),
+                MenuEntry::new(MenuAction::MainMenu, "Main Menu"),
+            ],
+            MenuPage::Settings => {
+                let mut entries: Vec<MenuEntry> = SettingKind::ALL
+                    .into_iter()
+                    .map(|kind| {
+                        MenuEntry::new(MenuAction::CycleSetting(kind), settings.describe(kind))
+                    })
+                    .collect();
+                entries.push(MenuEntry::new(MenuAction::Controls, "Controls"));
+                entries.push(MenuEntry::new(MenuAction::Back, "Back"));
+                entries
+            }
+            MenuPage::Controls => {
+                let mut entries: Vec<MenuEntry> = ToolActions::variants()
+                    .map(|action| {
+                        let status = match &rebind.conflict {
+                            _ if rebind.action != Some(action) => bindings.describe(action),
+                            Some((input, other)) => format!("{} is {}", input, other.label()),
+                            None => "Press input".to_string(),
+                        };
+                        MenuEntry::new(
+                            MenuAction::Rebind(action),
+                            format!("{}\n{}", action.label(), status),
+                        )
+                    })
+                    .collect();
+                entries.push(MenuEntry::new(MenuAction::ResetBindings, "Reset"));
+                entries.push(MenuEntry::new(MenuAction::Back, "Back"));
+                entries
+            }
+        }
+    }
+}
+
+/// moves the selection in a grid of `num` buttons with the given number of columns, the selection stays in the grid
+fn navigate(selected: usize, delta: IVec2, num: usize, columns: usize) -> usize {
+    if num == 0 {
+        return 0;
+    }
+    let columns = columns.max(1) as i32;
+    let next = selected as i32 + delta.x + delta.y * columns;
+    next.clamp(0, num as i32 - 1) as usize
+}
+
+/// shows the main menu when the main menu state is entered, the level select page stays if it was chosen in the
+/// pause menu
+pub fn enter_main_menu(mut menu_state: ResMut<MenuState>) {
+    if menu_state.page == MenuPage::LevelSelect {
+        menu_state.open = true;
+    } else {
+        *menu_state = MenuState::show(MenuPage::Main, 0);
+    }
+}
+
+/// closes the pause menu when a level is (re)started
+pub fn enter_level_close_menu(mut menu_state: ResMut<MenuState>) {
+    *menu_state = MenuState {
+        page: MenuPage::Pause,
+        selected: 0,
+        open: false,
+    };
+}
+
+/// spawns the page of the menu that is given by the [`MenuState`] if it is not shown yet
+#[allow(clippy::too_many_arguments)]
+pub fn menu_page_system(
+    mut commands: Commands,
+    query_menu: Query<(Entity, &UITagMenu)>,
+    menu_state: Res<MenuState>,
+    assets: Res<GameAssets>,
+    save: Res<SaveGame>,
+    settings: Res<Settings>,
+    bindings: Res<KeyBindings>,
+    rebind: Res<RebindState>,
+) {
+    let mut shown = false;
+    for (entity, tag) in query_menu.iter() {
+        // the settings and controls pages are spawned again to show the changed values
+        let outdated = match tag.page {
+            MenuPage::Settings => settings.is_changed(),
+            MenuPage::Controls => bindings.is_changed() || rebind.is_changed(),
+            _ => false,
+        };
+        if menu_state.open && tag.page == menu_state.page && !outdated {
+            shown = true;
+        } else {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    if shown || !menu_state.open {
+        return;
+    }
+    //~
+
+    let page = menu_state.page;
+    let columns = page.columns();
+    let button_width = match page {
+        _ if columns == 1 => 384.0,
+        MenuPage::Controls => 256.0,
+        _ => 192.0,
+    };
+    let txt_style = TextStyle {
+        font: assets.font.clone(),
+        // the bindings of the controls page need more space
+        font_size: if page == MenuPage::Controls {
+            20.0
+        } else {
+            28.0
+        },
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            // the pause overlay blocks the interaction with the toolbar below it
+            background_color: match page {
+                MenuPage::Pause => Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                _ => Color::NONE.into(),
+            },
+            focus_policy: match page {
+                MenuPage::Pause => FocusPolicy::Block,
+                _ => FocusPolicy::Pass,
+            },
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(Name::new("Menu"))
+        .insert(UITagMenu { page })
+        .with_children(|cb| {
+            cb.spawn(TextBundle::from_section(
+                match page {
+                    MenuPage::Main => "DISASTRIS",
+                    MenuPage::LevelSelect => "Level Select",
+                    MenuPage::Pause => "Paused",
+                    MenuPage::Settings => "Settings",
+                    MenuPage::Controls => "Controls",
+                },
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 64.0,
+                    color: Color::WHITE,
+                },
+            ));
+
+            cb.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px((button_width + 16.0) * columns as f32),
+                    flex_wrap: FlexWrap::Wrap,
+                    justify_content: JustifyContent::Center,
+                    margin: UiRect::top(Val::Px(32.0)),
+                    ..default()
+                },
+                focus_policy: FocusPolicy::Pass,
+                ..default()
+            })
+            .with_children(|cb| {
+                for (index, entry) in page
+                    .entries(&save, &settings, &bindings, &rebind)
+                    .into_iter()
+                    .enumerate()
+                {
+                    cb.spawn(ButtonBundle {
+                        style: Style {
+                            width: Val::Px(button_width),
+                            height: Val::Px(80.0),
+                            margin: UiRect::all(Val::Px(8.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: assets.normal_button_color.into(),
+                        ..default()
+                    })
+                    .insert(Name::new(format!("Menu Button: {:?}", entry.action)))
+                    .insert(UITagMenuButton {
+                        action: entry.action,
+                        index,
+                        enabled: entry.enabled,
+                    })
+                    .with_children(|cb| {
+                        let mut style = txt_style.clone();
+                        if !entry.enabled {
+                            style.color = Color::GRAY;
+                        }
+                        cb.spawn(
+                            TextBundle::from_section(entry.label, style)
+                                .with_text_alignment(TextAlignment::Center),
+                        );
+                    });
+                }
+            });
+        });
+}
+
+/// moves the selection of the menu and activates the selected button via mouse, keyboard and gamepad
+#[allow(clippy::too_many_arguments)]
+pub fn menu_navigation_system(
+    query_interaction: Query<(&Interaction, &UITagMenuButton), Changed<Interaction>>,
+    query_button: Query<&UITagMenuButton>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut menu_state: ResMut<MenuState>,
+    mut gs: ResMut<GameState>,
+    save: Res<SaveGame>,
+    mut settings: ResMut<Settings>,
+    mut bindings: ResMut<KeyBindings>,
+    mut rebind: ResMut<RebindState>,
+    mut tool_actions: ResMut<ActionState<ToolActions>>,
+    cur_state: Res<State<DisastrisAppState>>,
+    mut next_state: ResMut<NextState<DisastrisAppState>>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    // the input is bound by the [`menu_rebinding_system`]
+    if rebind.action.is_some() {
+        return;
+    }
+
+    let gamepad_pressed = |button_type: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    let pressed = |keys: &[KeyCode], button_type: GamepadButtonType| {
+        keyboard_input.any_just_pressed(keys.iter().copied()) || gamepad_pressed(button_type)
+    };
+    let back = pressed(&[KeyCode::Escape, KeyCode::Back], GamepadButtonType::East);
+
+    if !menu_state.open {
+        // the pause menu is opened by <ESC> or the start button of a gamepad while a level is played
+        let open = back || gamepad_pressed(GamepadButtonType::Start);
+        if open && *cur_state.get() == DisastrisAppState::PlayLevel {
+            *menu_state = MenuState::show(MenuPage::Pause, 0);
+        }
+        return;
+    }
+    //~
+
+    // the input that is used in the menu shall not trigger the actions of the level, e.g. a <SPACE> that resumes
+    // the level does not start the simulation
+    tool_actions.consume_all();
+
+    let mut delta = IVec2::ZERO;
+    if pressed(&[KeyCode::Up, KeyCode::W], GamepadButtonType::DPadUp) {
+        delta.y -= 1;
+    }
+    if pressed(&[KeyCode::Down, KeyCode::S], GamepadButtonType::DPadDown) {
+        delta.y += 1;
+    }
+    if pressed(&[KeyCode::Left, KeyCode::A], GamepadButtonType::DPadLeft) {
+        delta.x -= 1;
+    }
+    if pressed(&[KeyCode::Right, KeyCode::D], GamepadButtonType::DPadRight) {
+        delta.x += 1;
+    }
+
+    let num_buttons = query_button.iter().count();
+    if delta != IVec2::ZERO {
+        menu_state.selected = navigate(
+            menu_state.selected,
+            delta,
+            num_buttons,
+            menu_state.page.columns(),
+        );
+    }
+
+    let mut activated = None;
+    for (interaction, button) in query_interaction.iter() {
+        match *interaction {
+            Interaction::Hovered => menu_state.selected = button.index,
+            Interaction::Pressed => activated = Some(*button),
+            Interaction::None => {}
+        }
+    }
+    if pressed(&[KeyCode::Return, KeyCode::Space], GamepadButtonType::South) {
+        activated = query_button
+            .iter()
+            .find(|button| button.index == menu_state.selected)
+            .copied();
+    }
+    let back_action = match menu_state.page {
+        MenuPage::Main => None,
+        MenuPage::LevelSelect | MenuPage::Settings | MenuPage::Controls => Some(MenuAction::Back),
+        MenuPage::Pause => Some(MenuAction::Resume),
+    };
+    if let Some(action) = back_action.filter(|_| back) {
+        activated = Some(UITagMenuButton {
+            action,
+            index: 0,
+            enabled: true,
+        });
+    }
+
+    let action = match activated {
+        Some(button) if button.enabled => button.action,
+        _ => return,
+    };
+    //~
+
+    info!("Menu action {:?} activated", action);
+    match action {
+        MenuAction::Continue => start_level(
+            save.next_unsolved(Level::NUM_LEVELS),
+            &mut gs,
+            &mut next_state,
+        ),
+        MenuAction::LevelSelect => {
+            *menu_state = MenuState::show(MenuPage::LevelSelect, 0);
+            // the level select page of the pause menu is shown in the main menu
+            if *cur_state.get() != DisastrisAppState::Mainmenu {
+                next_state.set(DisastrisAppState::Mainmenu);
+            }
+        }
+        MenuAction::Back => {
+            // the settings are reached from the main menu and from the pause menu
+            *menu_state = match (menu_state.page, cur_state.get()) {
+                (MenuPage::Settings, DisastrisAppState::PlayLevel) => {
+                    MenuState::show(MenuPage::Pause, 3)
+                }
+                (MenuPage::Settings, _) => MenuState::show(MenuPage::Main, 3),
+                (MenuPage::Controls, _) => {
+                    MenuState::show(MenuPage::Settings, SettingKind::ALL.len())
+                }
+                _ => MenuState::show(MenuPage::Main, 1),
+            };
+        }
+        MenuAction::Settings => *menu_state = MenuState::show(MenuPage::Settings, 0),
+        MenuAction::CycleSetting(kind) => {
+            settings.cycle(kind);
+            settings.save();
+        }
+        MenuAction::Controls => *menu_state = MenuState::show(MenuPage::Controls, 0),
+        MenuAction::Rebind(action) => {
+            *rebind = RebindState {
+                action: Some(action),
+                conflict: None,
+            };
+        }
+        MenuAction::ResetBindings => {
+            bindings.reset();
+            bindings.save();
+        }
+        MenuAction::Resume => menu_state.open = false,
+        MenuAction::RestartLevel => {
+            // From Placeholder --> PlayLevel
+            menu_state.open = false;
+            next_state.set(DisastrisAppState::Placeholder);
+        }
+        MenuAction::MainMenu => {
+            *menu_state = MenuState::show(MenuPage::Main, 0);
+            next_state.set(DisastrisAppState::Mainmenu);
+        }
+        MenuAction::PlayLevel(num) => start_level(num, &mut gs, &mut next_state),
+        MenuAction::Quit => {
+            ev_exit.send(AppExit);
+        }
+        MenuAction::Editor => {
+            warn!("Menu action {:?} is not available yet", action);
+        }
+    }
+}
+
+/// binds the next input of the player to the action that waits for it on the controls page, <ESC> cancels
+pub fn menu_rebinding_system(
+    mut rebind: ResMut<RebindState>,
+    mut bindings: ResMut<KeyBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut waiting: Local<Option<ToolActions>>,
+) {
+    const MODIFIERS: [Modifier; 4] = [
+        Modifier::Control,
+        Modifier::Shift,
+        Modifier::Alt,
+        Modifier::Win,
+    ];
+
+    let Some(action) = rebind.action else {
+        *waiting = None;
+        mouse_wheel_events.clear();
+        return;
+    };
+    // the input that started the rebinding is not bound
+    if *waiting != Some(action) {
+        *waiting = Some(action);
+        mouse_wheel_events.clear();
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        *rebind = RebindState::default();
+        return;
+    }
+    //~
+
+    let is_modifier = |key: &KeyCode| MODIFIERS.iter().any(|m| m.key_codes().contains(key));
+    let pressed = keyboard_input
+        .get_just_pressed()
+        .find(|key| !is_modifier(key))
+        .map(|key| InputKind::Keyboard(*key))
+        .or_else(|| {
+            mouse_input
+                .get_just_pressed()
+                .next()
+                .map(|button| InputKind::Mouse(*button))
+        })
+        .or_else(|| {
+            mouse_wheel_events
+                .read()
+                .filter(|ev| ev.y != 0.0)
+                .last()
+                .map(|ev| match ev.y > 0.0 {
+                    true => InputKind::MouseWheel(MouseWheelDirection::Up),
+                    false => InputKind::MouseWheel(MouseWheelDirection::Down),
+                })
+        })
+        .or_else(|| {
+            gamepad_input
+                .get_just_pressed()
+                .next()
+                .map(|button| InputKind::GamepadButton(button.button_type))
+        });
+    let Some(pressed) = pressed else {
+        return;
+    };
+
+    // the modifiers that are held form a chord with the input, e.g. Control+Z
+    let input = UserInput::chord(
+        MODIFIERS
+            .into_iter()
+            .filter(|m| keyboard_input.any_pressed(m.key_codes()))
+            .map(InputKind::Modifier)
+            .chain([pressed]),
+    );
+    match bindings.bind(action, input.clone()) {
+        Ok(()) => {
+            info!("Bound {} to {:?}", format_input(&input), action);
+            bindings.save();
+            *rebind = RebindState::default();
+        }
+        Err(other) => rebind.conflict = Some((format_input(&input), other)),
+    }
+}
+
+fn start_level(num: u32, gs: &mut GameState, next_state: &mut NextState<DisastrisAppState>) {
+    gs.level = None;
+    gs.upcoming_level = Some(Level::new(num));
+    next_state.set(DisastrisAppState::PlayLevel);
+}
+
+/// highlights the selected button of the menu
+pub fn menu_highlight_system(
+    mut query_button: Query<(&mut BackgroundColor, &UITagMenuButton)>,
+    menu_state: Res<MenuState>,
+    assets: Res<GameAssets>,
+) {
+    for (mut color, button) in query_button.iter_mut() {
+        let selected = button.index == menu_state.selected;
+        *color = match (selected, button.enabled) {
+            (true, true) => assets.hover_button_color,
+            (true, false) => assets.selected_but_unavailable_button_color,
+            (false, true) => assets.normal_button_color,
+            (false, false) => assets.unavailable_button_color,
+        }
+        .into();
+    }
+}
+
+#[test]
+fn test_menu_navigate() {
+    // a grid with 4 columns and 6 buttons
+    assert_eq!(navigate(0, IVec2::new(1, 0), 6, 4), 1);
+    assert_eq!(navigate(1, IVec2::new(0, 1), 6, 4), 5);
+    assert_eq!(navigate(3, IVec2::new(0, 1), 6, 4), 5);
+    assert_eq!(navigate(0, IVec2::new(-1, 0), 6, 4), 0);
+    assert_eq!(navigate(2, IVec2::new(0, -1), 6, 1), 1);
+    assert_eq!(navigate(2, IVec2::ZERO, 0, 1), 0);
+}
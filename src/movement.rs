@@ -2,11 +2,14 @@ use crate::prelude::*;
 use crate::view::prelude::*;
 
 use crate::state::GameStateLevel;
-use bevy::{log, prelude::*};
+use bevy::{log, prelude::*, utils::HashMap};
 
 pub mod prelude {
+    pub use super::BlobCollision;
     pub use super::BlobMoveEvent;
+    pub use super::KickTable;
     pub use super::MoveDirection;
+    pub use super::Orientation;
     pub use super::RotateDirection;
 }
 
@@ -67,11 +70,17 @@ impl From<MoveDirection> for IVec2 {
 }
 
 /// The rotation of an element in respect to a [`Field`]
+///
+/// The `*AroundTool` variants do not rotate around the pivot of the blob but around the
+/// cell of the tool, they are triggered as soon as any block of the blob touches the tool.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
 pub enum RotateDirection {
     #[default]
     Left = 1,
     Right = 2,
+    Half = 3,
+    LeftAroundTool = 4,
+    RightAroundTool = 5,
 }
 
 impl TryFrom<i32> for RotateDirection {
@@ -81,6 +90,11 @@ impl TryFrom<i32> for RotateDirection {
         match value {
             x if x == RotateDirection::Left as i32 => Ok(RotateDirection::Left),
             x if x == RotateDirection::Right as i32 => Ok(RotateDirection::Right),
+            x if x == RotateDirection::Half as i32 => Ok(RotateDirection::Half),
+            x if x == RotateDirection::LeftAroundTool as i32 => Ok(RotateDirection::LeftAroundTool),
+            x if x == RotateDirection::RightAroundTool as i32 => {
+                Ok(RotateDirection::RightAroundTool)
+            }
             _ => Err(()),
         }
     }
@@ -92,10 +106,125 @@ impl RotateDirection {
     }
 
     pub fn max() -> i32 {
-        2
+        5
+    }
+
+    /// the rotation that is applied on the blocks of a blob
+    pub fn rotation(&self) -> Rotation {
+        match self {
+            RotateDirection::Left | RotateDirection::LeftAroundTool => Rotation::Left,
+            RotateDirection::Right | RotateDirection::RightAroundTool => Rotation::Right,
+            RotateDirection::Half => Rotation::Half,
+        }
+    }
+
+    /// true if the rotation center is the tool cell and not the pivot of the blob
+    pub fn is_around_tool(&self) -> bool {
+        matches!(
+            self,
+            RotateDirection::LeftAroundTool | RotateDirection::RightAroundTool
+        )
     }
 }
 
+/// The orientation of an element in steps of 90 degrees counter-clock wise, e.g. of a placed cutter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
+pub enum Orientation {
+    #[default]
+    Deg0 = 0,
+    Deg90 = 1,
+    Deg180 = 2,
+    Deg270 = 3,
+}
+
+impl TryFrom<i32> for Orientation {
+    type Error = ();
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            x if x == Orientation::Deg0 as i32 => Ok(Orientation::Deg0),
+            x if x == Orientation::Deg90 as i32 => Ok(Orientation::Deg90),
+            x if x == Orientation::Deg180 as i32 => Ok(Orientation::Deg180),
+            x if x == Orientation::Deg270 as i32 => Ok(Orientation::Deg270),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Orientation {
+    pub fn min() -> i32 {
+        0
+    }
+
+    pub fn max() -> i32 {
+        3
+    }
+
+    /// the orientation after it has been turned by the given rotation
+    pub fn rotated(self, rotation: Rotation) -> Self {
+        let steps = match rotation {
+            Rotation::Left => 1,
+            Rotation::Right => 3,
+            Rotation::Half => 2,
+        };
+        ((self as i32 + steps) % 4)
+            .try_into()
+            .expect("modulo 4 always gives a valid orientation")
+    }
+
+    /// rotates a coordinate relative to a pivot into this orientation
+    pub fn apply(self, coord: IVec2) -> IVec2 {
+        (0..self as i32).fold(coord, |c, _| rotate_coord(c, Rotation::Left))
+    }
+
+    pub fn to_radians(self) -> f32 {
+        (self as i32 as f32 * 90.0).to_radians()
+    }
+}
+
+/// Offsets that are tried in the given order if a rotated blob would overlap occupied cells (wall kicks).
+/// If none of the offsets leads to a valid placement the rotation is cancelled.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct KickTable {
+    pub offsets: Vec<IVec2>,
+}
+
+impl Default for KickTable {
+    fn default() -> Self {
+        KickTable {
+            offsets: vec![
+                IVec2::ZERO,
+                IVec2::new(-1, 0),
+                IVec2::new(1, 0),
+                IVec2::new(0, -1),
+            ],
+        }
+    }
+}
+
+impl KickTable {
+    /// a kick table that cancels every rotation that would overlap occupied cells
+    pub fn without_kicks() -> Self {
+        KickTable {
+            offsets: vec![IVec2::ZERO],
+        }
+    }
+}
+
+/// Decides what happens if a moving blob would enter cells that are occupied by another blob
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Default)]
+pub enum BlobCollision {
+    /// blobs move over each other
+    #[default]
+    Pass,
+
+    /// a blob waits until the cells that are occupied by other blobs are free again
+    Block,
+
+    /// a blob pushes the other blobs in its movement direction, if they cannot move the blob waits
+    Push,
+}
+
 /// A event that indicates that the Blob shall be moved, can be dispatched by
 /// gravity, input and tools. @TODO - Think about an own schedule for the blob movement
 /// as a movement of a blob may lead to new movement events that need to be processed
@@ -110,149 +239,297 @@ pub struct BlobMoveEvent {
     entity: Entity,
 }
 
+/// Sends the movement events of all active blobs. The blobs that lead in their movement direction are moved
+/// first, such that the order in which collisions between blobs are resolved is deterministic.
 pub fn move_events_by_gravity_system(
-    query_collector: Query<(Entity, &GridBody)>,
-    mut query: Query<(Entity, &Blob)>,
+    query: Query<(Entity, &Blob, &GridBody)>,
     turn: Res<GameStateLevel>,
     mut ev: EventWriter<BlobMoveEvent>,
 ) {
     if turn.is_new_turn() {
-        let mut vec: Vec<(Entity, i32)> = query_collector
+        let mut vec: Vec<(Entity, IVec2, IVec2)> = query
             .iter()
-            .map(|(entity, body)| (entity, body.pivot.y))
+            .filter(|(_, blob, _)| blob.active)
+            .map(|(entity, blob, body)| (entity, body.pivot, blob.movement))
             .collect();
 
-        vec.sort_by(|left, right| left.1.cmp(&right.1));
-
-        for (id, _) in vec {
-            if let Ok((e, blob)) = query.get_mut(id) {
-                if blob.active {
-                    ev.send(BlobMoveEvent {
-                        delta: IVec2::new(blob.movement.x, blob.movement.y),
-                        entity: e,
-                    });
-                }
-            }
+        vec.sort_by_key(|(entity, pivot, movement)| {
+            (
+                std::cmp::Reverse(pivot.dot(*movement)),
+                std::cmp::Reverse(pivot.y),
+                pivot.x,
+                *entity,
+            )
+        });
+
+        for (entity, _, movement) in vec {
+            ev.send(BlobMoveEvent {
+                delta: movement,
+                entity,
+            });
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_move_blob_events(
     mut commands: Commands,
     mut query: Query<(Entity, &Blob, &mut GridBody)>,
-    field_query: Query<(Entity, &Field)>,
+    factory_query: Query<(Entity, &Field), With<FactoryFieldTag>>,
+    production_query: Query<(Entity, &Field), With<ProductionFieldTag>>,
     mut block_query: Query<(Entity, &mut Block)>,
     mut ev_move: EventReader<BlobMoveEvent>,
     mut ev_view: EventWriter<ViewUpdate>,
+    collision: Res<BlobCollision>,
 ) {
-    let (_field_id, field) = if let Ok(pair) = field_query.get_single() {
+    let (factory_id, factory) = if let Ok(pair) = factory_query.get_single() {
         pair
     } else {
         return;
     };
+    let production = production_query.get_single().ok();
     //~
 
+    // the cells of the blobs are tracked per field during this turn as the field state is not updated between
+    // the moves
+    let mut occupancy = blob_occupancy(block_query.iter().map(|(_, block)| block), |id| {
+        query.contains(id)
+    });
+    let mut settled: HashMap<Entity, Vec<IVec2>> = HashMap::new();
+    let mut pushed: Vec<Entity> = vec![];
+
     for ev in ev_move.read() {
-        if let Ok((blob_id, blob, mut body)) = query.get_mut(ev.entity) {
-            if blob.active && !body.transferred {
-                log::info!("Move Factory!");
-
-                // get block positions of the blob
-                let occ_coords: Vec<IVec2> = block_query
-                    .iter_mut()
-                    .filter(|(_, block)| block.group == Some(blob_id))
-                    .map(|(_, block)| block.position)
-                    .collect();
-
-                // transform blocks with given delta
-                let mut occ_coords_delta = occ_coords.clone();
-                for ch in &mut occ_coords_delta {
-                    *ch += ev.delta;
+        // a pushed blob already moved in this turn
+        if pushed.contains(&ev.entity) {
+            continue;
+        }
+        let is_movable = |id: Entity| {
+            query
+                .get(id)
+                .map(|(_, blob, _)| blob.active)
+                .unwrap_or(false)
+        };
+        if !is_movable(ev.entity) {
+            continue;
+        }
+        //~
+
+        log::info!("Move Factory!");
+        let blob_id = ev.entity;
+        let transferred = query
+            .get(blob_id)
+            .is_ok_and(|(_, _, body)| body.transferred);
+        let (field_id, field) = match (transferred, production) {
+            (true, Some(pair)) => pair,
+            _ => (factory_id, factory),
+        };
+        let field_occupancy = occupancy.entry(field_id).or_default();
+        let cells = field_occupancy.get(&blob_id).cloned().unwrap_or_default();
+        let field_settled = settled.entry(field_id).or_default();
+
+        // depending on the state at the target position of the grid decide how the movement happens
+        if !can_enter(field, field_settled, &cells, ev.delta) {
+            // a blob that leaves the bottom of the factory field continues on the production field
+            if let Some((prod_id, prod_field)) = production.filter(|_| !transferred) {
+                let offset = ev.delta - IVec2::new(0, factory.movable_size.1 as i32);
+                let leaves_bottom = cells
+                    .iter()
+                    .any(|pos| (*pos + ev.delta).y >= factory.movable_size.1 as i32);
+                let prod_settled = settled.get(&prod_id).cloned().unwrap_or_default();
+                if leaves_bottom && can_enter(prod_field, &prod_settled, &cells, offset) {
+                    log::info!("Transfer blob {:?} to the production field", blob_id);
+                    if let Ok((_, _, mut body)) = query.get_mut(blob_id) {
+                        let block_iter = block_query
+                            .iter_mut()
+                            .filter(|(_, block)| block.group == Some(blob_id));
+                        transfer_blob(
+                            blob_id,
+                            &mut body,
+                            prod_id,
+                            offset,
+                            block_iter,
+                            Some(&mut ev_view),
+                        );
+                    }
+                    let cells = occupancy
+                        .get_mut(&factory_id)
+                        .and_then(|o| o.remove(&blob_id))
+                        .unwrap_or_default();
+                    occupancy.entry(prod_id).or_default().insert(
+                        blob_id,
+                        cells.into_iter().map(|cell| cell + offset).collect(),
+                    );
+                    continue;
                 }
+            }
 
-                // check if any coordinate of the movement target is already occupied, e.g by a previous blob
-                let state = field.get_field_state();
-                let mut do_move =
-                    !state.is_any_coordinate(&occ_coords_delta, Some(&occ_coords), &|el| match el
-                        .kind
-                    {
-                        FieldElementKind::Empty
-                        | FieldElementKind::OutOfMovableRegion
-                        | FieldElementKind::Tool(_) => false,
-                        FieldElementKind::Block(id) if id.is_some() => false,
-                        _ => true,
-                    }) || blob.cutout;
-
-                // hack: we don't want the cutout blobs to interfer with the playfield therefore we move them away
-                let delta = if blob.cutout {
-                    IVec2::new(-2, 3 * ev.delta.y)
-                } else {
-                    ev.delta
-                };
-
-                // depending on the state at the target position of the grid decide how the movement happens
-                // here we just check for the pivot position
-                if !blob.cutout || do_move {
-                    do_move = handle_move(&mut body, delta, field, &mut block_query);
-                }
+            log::info!("Full Stop and occupy");
+            let block_iter = block_query
+                .iter_mut()
+                .filter(|(_, block)| block.group == Some(blob_id));
+            dissolve_blob(&mut commands, blob_id, block_iter, Some(&mut ev_view));
+            let cells = field_occupancy.remove(&blob_id).unwrap_or_default();
+            settled.entry(field_id).or_default().extend(cells);
+            continue;
+        }
 
-                // if flag do_move is set perform the actual move
-                let block_iter = block_query
-                    .iter_mut()
-                    .filter(|(_, block)| block.group == Some(blob_id));
-                if do_move {
-                    move_blob(blob_id, &mut body, delta, block_iter, Some(&mut ev_view));
-                } else {
-                    log::info!("Full Stop and occupy");
-                    dissolve_blob(&mut commands, blob_id, block_iter, Some(&mut ev_view));
+        let movers = collision_movers(
+            *collision,
+            field,
+            field_settled,
+            field_occupancy,
+            &is_movable,
+            blob_id,
+            ev.delta,
+        );
+
+        if let Some(movers) = movers {
+            for mover in movers {
+                if let Ok((_, _, mut body)) = query.get_mut(mover) {
+                    let block_iter = block_query
+                        .iter_mut()
+                        .filter(|(_, block)| block.group == Some(mover));
+                    move_blob(mover, &mut body, ev.delta, block_iter, Some(&mut ev_view));
+                }
+                if let Some(cells) = occupancy.entry(field_id).or_default().get_mut(&mover) {
+                    cells.iter_mut().for_each(|cell| *cell += ev.delta);
+                }
+                if mover != blob_id {
+                    pushed.push(mover);
                 }
             }
+        } else {
+            log::info!("Blob {:?} is blocked by another blob and waits", blob_id);
         }
     }
 }
 
-fn handle_move(
-    body: &mut GridBody,
+/// gets the cells of the blobs per field, blocks whose group is no blob (e.g. the blocks of tools) are left out
+fn blob_occupancy<'a>(
+    blocks: impl Iterator<Item = &'a Block>,
+    is_blob: impl Fn(Entity) -> bool,
+) -> HashMap<Entity, HashMap<Entity, Vec<IVec2>>> {
+    let mut occupancy: HashMap<Entity, HashMap<Entity, Vec<IVec2>>> = HashMap::new();
+    for block in blocks {
+        if let Some(blob_id) = block.group.filter(|id| is_blob(*id)) {
+            occupancy
+                .entry(block.field)
+                .or_default()
+                .entry(blob_id)
+                .or_default()
+                .push(block.position);
+        }
+    }
+    occupancy
+}
+
+/// gets the blobs that move by `delta` together with the given blob under the [`BlobCollision`] rule, i.e. the
+/// blob itself and the blobs it pushes. Returns `None` if the blob has to wait.
+fn collision_movers(
+    collision: BlobCollision,
+    field: &Field,
+    settled: &[IVec2],
+    occupancy: &HashMap<Entity, Vec<IVec2>>,
+    is_movable: &dyn Fn(Entity) -> bool,
+    blob_id: Entity,
+    delta: IVec2,
+) -> Option<Vec<Entity>> {
+    match collision {
+        BlobCollision::Pass => Some(vec![blob_id]),
+        BlobCollision::Block => {
+            let cells = occupancy.get(&blob_id).cloned().unwrap_or_default();
+            blobs_at(occupancy, &cells, delta, blob_id)
+                .is_empty()
+                .then(|| vec![blob_id])
+        }
+        BlobCollision::Push => {
+            let mut chain = vec![blob_id];
+            collect_push_chain(field, settled, occupancy, is_movable, delta, &mut chain)
+                .then_some(chain)
+        }
+    }
+}
+
+/// gets the blobs other than `except` that occupy the target cells of a movement by `delta`
+fn blobs_at(
+    occupancy: &HashMap<Entity, Vec<IVec2>>,
+    cells: &[IVec2],
     delta: IVec2,
+    except: Entity,
+) -> Vec<Entity> {
+    let mut reval: Vec<Entity> = occupancy
+        .iter()
+        .filter(|(id, other_cells)| {
+            **id != except
+                && cells
+                    .iter()
+                    .any(|cell| other_cells.contains(&(*cell + delta)))
+        })
+        .map(|(id, _)| *id)
+        .collect();
+    reval.sort();
+    reval
+}
+
+/// Adds all the blobs that are pushed by the last blob in `chain` to the chain, returns false if one
+/// of the blobs in the chain cannot move
+fn collect_push_chain(
     field: &Field,
-    block_query: &mut Query<(Entity, &mut Block)>,
+    settled: &[IVec2],
+    occupancy: &HashMap<Entity, Vec<IVec2>>,
+    is_movable: &dyn Fn(Entity) -> bool,
+    delta: IVec2,
+    chain: &mut Vec<Entity>,
 ) -> bool {
-    let mut do_move = false;
+    let pusher = *chain.last().unwrap();
+    let cells = occupancy.get(&pusher).cloned().unwrap_or_default();
+    if !can_enter(field, settled, &cells, delta) {
+        return false;
+    }
 
+    for other in blobs_at(occupancy, &cells, delta, pusher) {
+        if chain.contains(&other) {
+            continue;
+        }
+        if !is_movable(other) {
+            return false;
+        }
+        chain.push(other);
+        if !collect_push_chain(field, settled, occupancy, is_movable, delta, chain) {
+            return false;
+        }
+    }
+    true
+}
+
+/// checks if the given cells can be moved by `delta` without respecting other moving blobs
+pub fn can_enter(field: &Field, settled: &[IVec2], cells: &[IVec2], delta: IVec2) -> bool {
     let state = field.get_field_state();
-    let rel_pos = body.get_relative_positions(block_query);
-    let do_move = rel_pos.iter().all(|pos| {
-        let ap = *pos + body.pivot + delta;
+    cells.iter().all(|pos| {
+        let ap = *pos + delta;
+        if settled.contains(&ap) {
+            return false;
+        }
 
         if let Some(element) = state.get_element(ap) {
             match element.kind {
-                FieldElementKind::Block(by_id) => {
-                    do_move = by_id.is_some(); // blobs are allowed to move over each other!
-                }
-                FieldElementKind::Empty => {
-                    do_move = true;
-                }
+                // moving blobs are handled by the BlobCollision rule
+                FieldElementKind::Block(by_id) => by_id.is_some(),
+                FieldElementKind::Empty | FieldElementKind::Tool(_) => true,
                 FieldElementKind::OutOfMovableRegion => {
                     // only react on outside of x movable region
-                    log::info!("{} < {}?", ap.y, field.movable_size.1);
-                    do_move = ap.y < field.movable_size.1 as i32;
-                }
-                FieldElementKind::Tool(_) => {
-                    do_move = true;
+                    ap.y < field.movable_size.1 as i32
                 }
                 _ => {
                     bevy::log::warn!("Do nothing with target: {} but stuck", ap);
+                    false
                 }
             }
         } else {
             // we allow to leave the field on the top
-            do_move = ap.y < 0;
+            ap.y < 0
         }
-
-        do_move
-    });
-
-    do_move
+    })
 }
 
 pub fn dissolve_blob<'a>(
@@ -268,6 +545,28 @@ pub fn dissolve_blob<'a>(
     }
 }
 
+/// moves the blob by `delta` into the coordinate system of the given production field
+pub fn transfer_blob<'a>(
+    blob_id: Entity,
+    body: &mut GridBody,
+    field: Entity,
+    delta: IVec2,
+    block_iter: impl Iterator<Item = (Entity, Mut<'a, Block>)>,
+    ev_view: Option<&mut EventWriter<ViewUpdate>>,
+) {
+    body.pivot += delta;
+    body.transferred = true;
+
+    for (_id, mut block) in block_iter {
+        block.position += delta;
+        block.field = field;
+    }
+
+    if let Some(ev_view) = ev_view {
+        ev_view.send(ViewUpdate::BlobTransferred(blob_id));
+    }
+}
+
 pub fn move_blob<'a>(
     blob_id: Entity,
     body: &mut GridBody,
@@ -292,3 +591,113 @@ pub fn move_blob<'a>(
         ev_view.send(ViewUpdate::BlobMoved(blob_id));
     }
 }
+
+#[test]
+fn test_blobs_at_target_cells() {
+    let (a, b, c) = (
+        Entity::from_raw(1),
+        Entity::from_raw(2),
+        Entity::from_raw(3),
+    );
+    let mut occupancy = HashMap::new();
+    occupancy.insert(a, vec![IVec2::new(0, 0), IVec2::new(1, 0)]);
+    occupancy.insert(b, vec![IVec2::new(0, 1)]);
+    occupancy.insert(c, vec![IVec2::new(5, 5)]);
+
+    let cells = occupancy[&a].clone();
+    assert_eq!(blobs_at(&occupancy, &cells, IVec2::new(0, 1), a), vec![b]);
+    assert!(blobs_at(&occupancy, &cells, IVec2::new(0, -1), a).is_empty());
+    assert!(blobs_at(&occupancy, &cells, IVec2::ZERO, a).is_empty());
+}
+
+#[test]
+fn test_collision_movers() {
+    let (a, b, c, tool) = (
+        Entity::from_raw(1),
+        Entity::from_raw(2),
+        Entity::from_raw(3),
+        Entity::from_raw(4),
+    );
+    let field_id = Entity::from_raw(10);
+    let mut field = Field::as_factory();
+    field.set_field_state(FieldState::new(field.bounds()));
+    let down = IVec2::new(0, 1);
+
+    // a is above b which is above c, a cutter lies below a and b, a dissolved block is below c
+    let block = |x, y, group| Block {
+        position: IVec2::new(x, y),
+        relative_position: None,
+        group,
+        field: field_id,
+        color: crate::data::bodies::BlockColor::default(),
+    };
+    let blocks = [
+        block(4, 5, Some(a)),
+        block(5, 5, Some(a)),
+        block(5, 6, Some(b)),
+        block(5, 7, Some(c)),
+        block(6, 7, Some(c)),
+        block(4, 6, Some(tool)),
+        block(3, 6, Some(tool)),
+        block(6, 9, None),
+    ];
+    let occupancy = blob_occupancy(blocks.iter(), |id| id != tool);
+    assert_eq!(occupancy.len(), 1);
+    let occupancy = &occupancy[&field_id];
+    assert_eq!(occupancy.len(), 3);
+    assert!(!occupancy.contains_key(&tool));
+
+    let all_movable = |_| true;
+    let movers = |collision, settled: &[IVec2], is_movable: &dyn Fn(Entity) -> bool, blob| {
+        collision_movers(
+            collision, &field, settled, occupancy, is_movable, blob, down,
+        )
+    };
+
+    assert_eq!(
+        movers(BlobCollision::Pass, &[], &all_movable, a),
+        Some(vec![a])
+    );
+    // b is in the way of a but c can move freely
+    assert_eq!(movers(BlobCollision::Block, &[], &all_movable, a), None);
+    assert_eq!(
+        movers(BlobCollision::Block, &[], &all_movable, c),
+        Some(vec![c])
+    );
+    // a pushes b which pushes c
+    assert_eq!(
+        movers(BlobCollision::Push, &[], &all_movable, a),
+        Some(vec![a, b, c])
+    );
+    // b moves onto the cutter like onto an empty cell
+    assert_eq!(
+        collision_movers(
+            BlobCollision::Block,
+            &field,
+            &[],
+            occupancy,
+            &all_movable,
+            b,
+            IVec2::new(-1, 0)
+        ),
+        Some(vec![b])
+    );
+    assert_eq!(
+        collision_movers(
+            BlobCollision::Push,
+            &field,
+            &[],
+            occupancy,
+            &all_movable,
+            b,
+            IVec2::new(-1, 0)
+        ),
+        Some(vec![b])
+    );
+    // the chain stops if its last blob is blocked by a settled block or cannot move
+    assert_eq!(
+        movers(BlobCollision::Push, &[IVec2::new(6, 8)], &all_movable, a),
+        None
+    );
+    assert_eq!(movers(BlobCollision::Push, &[], &|id| id != c, a), None);
+}
@@ -0,0 +1,676 @@
+//! The preview module draws what would happen before the player commits to it, i.e. a ghost of the selected tool
+//! under the cursor and the predicted trajectories of the blobs through the current tool layout.
+//!
+//! The trajectories are predicted by a dry-run of the movement rules, see [`predict_trajectory`]. It follows
+//! every blob on its own, so blobs that collide with each other or that are spawned later are not considered.
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::field::ProductionFieldTag;
+use crate::input::{check_placement, check_relocation, Placement, ToolDrag};
+use crate::movement::{can_enter, prelude::*};
+use crate::prelude::*;
+use crate::render_old::RenderableGrid;
+use crate::state::GameStateLevel;
+use crate::view::rotate_coord;
+use crate::{PX_PER_TILE, Z_OVERLAY};
+
+/// The maximum number of turns of a predicted trajectory, e.g. a blob that leaves the field on the top never stops
+const MAX_PREDICTED_TURNS: usize = 128;
+
+/// The size of the marks of the turns of a trajectory in pixels
+const TRAJECTORY_MARK_SIZE: f32 = 8.0;
+
+/// The tint of the ghost if the tool cannot be placed at the cursor
+const GHOST_INVALID_TINT: Color = Color::rgba(1.0, 0.2, 0.2, 0.5);
+
+/// The alpha of the ghost if the tool can be placed at the cursor
+const GHOST_ALPHA: f32 = 0.5;
+
+/// A semi-transparent preview of the selected tool at the placement coordinate, it is respawned whenever the
+/// tool, the coordinate or the outcome of the placement changes
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct ToolGhost {
+    /// the tool that would be placed, or the eraser
+    pub tool: Tool,
+
+    /// the coordinate on the factory field the tool would be placed at
+    pub coordinate: IVec2,
+
+    /// false if the placement would be rejected, the ghost is tinted red then
+    pub valid: bool,
+
+    /// the tool that would be replaced or erased and refunded to the inventory
+    pub refunded: Option<Tool>,
+}
+
+impl ToolGhost {
+    /// the ghost of the given tool, `None` if the tool is not shown on the field, e.g. the play button
+    pub fn new(tool: Tool, coordinate: IVec2, placement: Placement) -> Option<Self> {
+        let shown = matches!(
+            tool,
+            Tool::Move(_) | Tool::Rotate(_) | Tool::Cutter(_, _) | Tool::Painter(_) | Tool::Eraser
+        );
+        shown.then(|| ToolGhost {
+            tool,
+            coordinate,
+            valid: placement.valid,
+            refunded: placement.replaced.map(|(_, tool, _)| tool),
+        })
+    }
+
+    /// the color of the sprites of the ghost
+    pub fn tint(&self, assets: &GameAssets) -> Color {
+        if self.valid {
+            assets.get_tool_tint(self.tool).with_a(GHOST_ALPHA)
+        } else {
+            GHOST_INVALID_TINT
+        }
+    }
+}
+
+/// Keeps the [`ToolGhost`] in sync with the selected tool and the placement coordinate, while a placed tool is
+/// dragged the ghost shows where it is moved to
+#[allow(clippy::too_many_arguments)]
+pub fn tool_ghost_system(
+    mut commands: Commands,
+    ghost_query: Query<(Entity, &ToolGhost)>,
+    field_query: Query<(Entity, &Field), With<FactoryFieldTag>>,
+    query_tool: Query<&Tool>,
+    query_body: Query<&GridBody>,
+    player_state: Res<PlayerStateLevel>,
+    drag: Res<ToolDrag>,
+    constraints: Res<LevelConstraints>,
+    assets: Res<GameAssets>,
+    cutter_shapes: Res<CutterShapes>,
+) {
+    let Ok((field_id, field)) = field_query.get_single() else {
+        return;
+    };
+
+    let expected = player_state.tool_placement_coordinate.and_then(|coord| {
+        match drag.relocated(coord, field) {
+            Some((entity, tool, _)) => ToolGhost::new(
+                tool,
+                coord,
+                check_relocation(entity, tool, coord, field, &cutter_shapes),
+            ),
+            None => {
+                let tool = player_state.selected_tool?;
+                let placement = check_placement(
+                    tool,
+                    coord,
+                    field,
+                    &player_state,
+                    &constraints,
+                    &cutter_shapes,
+                    &query_tool,
+                    &query_body,
+                );
+                ToolGhost::new(tool, coord, placement)
+            }
+        }
+    });
+
+    let current = ghost_query.get_single().ok();
+    if current.map(|(_, ghost)| *ghost) == expected {
+        return;
+    }
+    if let Some((id, _)) = current {
+        commands.entity(id).despawn_recursive();
+    }
+    if let Some(ghost) = expected {
+        spawn_tool_ghost(
+            &mut commands,
+            ghost,
+            field_id,
+            field,
+            &assets,
+            &cutter_shapes,
+        );
+    }
+}
+
+/// spawns the [`ToolGhost`] as a child of the factory field, cutters show all cells of their shape
+pub fn spawn_tool_ghost(
+    commands: &mut Commands,
+    ghost: ToolGhost,
+    field_id: Entity,
+    field: &Field,
+    assets: &GameAssets,
+    cutter_shapes: &CutterShapes,
+) -> Entity {
+    let (px, py) = field.coords_to_px(ghost.coordinate.x, ghost.coordinate.y);
+    let color = ghost.tint(assets);
+
+    let mut icon_transform = Transform::default();
+    let mut cells = vec![];
+    if let Tool::Cutter(shape, orientation) = ghost.tool {
+        icon_transform.rotate_z(orientation.to_radians());
+        if let Some(definition) = cutter_shapes.get(shape) {
+            cells = definition
+                .body
+                .get_relative_positions()
+                .into_iter()
+                .map(|pos| orientation.apply(pos))
+                .filter(|pos| *pos != IVec2::ZERO)
+                .collect();
+        }
+    }
+
+    let id = commands
+        .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+            px,
+            py,
+            Z_OVERLAY + 0.5,
+        )))
+        .insert(ghost)
+        .insert(Name::new(format!("Ghost-{}", ghost.tool)))
+        .with_children(|cb| {
+            cb.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::ONE * PX_PER_TILE - 2.0),
+                    ..Default::default()
+                },
+                transform: icon_transform,
+                texture: assets.get_tool_image(ghost.tool).clone(),
+                ..Default::default()
+            });
+
+            // y grows downwards on the field but upwards in the world
+            for cell in cells {
+                cb.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::ONE * PX_PER_TILE - 2.0),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(
+                        cell.x as f32 * PX_PER_TILE,
+                        -cell.y as f32 * PX_PER_TILE,
+                        0.0,
+                    ),
+                    ..Default::default()
+                });
+            }
+
+            if let Some(refunded) = ghost.refunded {
+                cb.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        format!("+1 {}", refunded),
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, PX_PER_TILE * 0.75, 1.0),
+                    ..Default::default()
+                });
+            }
+        })
+        .id();
+    commands.entity(field_id).push_children(&[id]);
+
+    id
+}
+
+/// What happens to a blob at the end of its predicted trajectory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum TrajectoryEnd {
+    /// the blob cannot move any further and dissolves into blocks
+    Dissolved,
+
+    /// the blob leaves the bottom of the factory and continues on the production field
+    Transferred,
+
+    /// every block of the blob has been removed by a trimmer
+    Trimmed,
+
+    /// the blob runs in circles or does not stop within [`MAX_PREDICTED_TURNS`]
+    Endless,
+}
+
+/// The position of the pivot of a blob in a turn of its predicted trajectory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct TrajectoryStep {
+    pub pivot: IVec2,
+
+    /// true if a rotation tool turned the blob in this turn
+    pub rotated: bool,
+
+    /// true if a cutter cut blocks of the blob in this turn
+    pub cut: bool,
+}
+
+/// The predicted path of a blob, the first step is the current position of the blob
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect)]
+pub struct Trajectory {
+    pub steps: Vec<TrajectoryStep>,
+    pub end: TrajectoryEnd,
+}
+
+/// The tool layout of the factory field as it is seen by the movement rules
+pub struct ToolLayout<'a> {
+    pub factory: &'a Field,
+
+    /// the production field that receives the blobs that leave the bottom of the factory, if any
+    pub production: Option<&'a Field>,
+
+    /// the tools by the cells they occupy, a cutter occupies all cells of its shape
+    pub tools: HashMap<IVec2, Tool>,
+
+    /// the cells of the placed cutters in the orientation of the cutter together with their mode
+    pub cutters: Vec<(Vec<IVec2>, CutterMode)>,
+
+    pub kicks: &'a KickTable,
+}
+
+/// Predicts the trajectory of a blob on the factory field in a dry-run of the movement rules, i.e. the tools are
+/// applied in the same order as by [`crate::field::tool::apply_movement_tools`] and
+/// [`crate::field::tool::apply_cutter_tool`] before the blob is moved. A cut follows the remainder of the blob.
+pub fn predict_trajectory(
+    layout: &ToolLayout,
+    mut pivot: IVec2,
+    mut cells: Vec<IVec2>,
+    mut movement: IVec2,
+) -> Trajectory {
+    let mut steps = vec![TrajectoryStep {
+        pivot,
+        rotated: false,
+        cut: false,
+    }];
+    let mut visited = HashSet::new();
+
+    for _ in 0..MAX_PREDICTED_TURNS {
+        // the same blob at the same place with the same movement runs in circles
+        if !visited.insert((pivot, movement, cells.clone())) {
+            break;
+        }
+        //~
+
+        let mut step = TrajectoryStep {
+            pivot,
+            rotated: false,
+            cut: false,
+        };
+
+        // 1. the tools at the pivot change the movement or rotate the blob
+        let rotation = match layout.tools.get(&pivot) {
+            Some(Tool::Move(d)) => {
+                movement = (*d).into();
+                None
+            }
+            Some(Tool::Rotate(d)) if !d.is_around_tool() => Some((*d, pivot)),
+            _ => cells.iter().find_map(|pos| match layout.tools.get(pos) {
+                Some(Tool::Rotate(d)) if d.is_around_tool() => Some((*d, *pos)),
+                _ => None,
+            }),
+        };
+        if let Some((d, center)) = rotation {
+            let body = GridBody::new(pivot);
+            let relative: Vec<IVec2> = cells.iter().map(|pos| *pos - pivot).collect();
+            let rotation = d.rotation();
+            if let Some(kick) =
+                body.find_rotation_kick(&relative, rotation, center, layout.factory, layout.kicks)
+            {
+                pivot = body.rotated_pivot(rotation, center, kick);
+                cells = relative
+                    .iter()
+                    .map(|rp| pivot + rotate_coord(*rp, rotation))
+                    .collect();
+                step.rotated = true;
+            }
+        }
+
+        // 2. the cutters remove the covered blocks
+        for (cutter_cells, mode) in layout.cutters.iter() {
+            let covered = cells
+                .iter()
+                .filter(|pos| cutter_cells.contains(pos))
+                .count();
+            let is_whole_blob = covered == cells.len();
+            if !mode.fires(covered, cutter_cells.len()) || (is_whole_blob && !mode.is_trimmer()) {
+                continue;
+            }
+            step.cut = true;
+            cells.retain(|pos| !cutter_cells.contains(pos));
+            if cells.is_empty() {
+                step.pivot = pivot;
+                steps.push(step);
+                return Trajectory {
+                    steps,
+                    end: TrajectoryEnd::Trimmed,
+                };
+            }
+        }
+
+        // 3. the blob moves or stops
+        if !can_enter(layout.factory, &[], &cells, movement) {
+            let offset = movement - IVec2::new(0, layout.factory.movable_size.1 as i32);
+            let leaves_bottom = cells
+                .iter()
+                .any(|pos| (*pos + movement).y >= layout.factory.movable_size.1 as i32);
+            let transferred = leaves_bottom
+                && layout
+                    .production
+                    .is_some_and(|production| can_enter(production, &[], &cells, offset));
+            step.pivot = pivot;
+            steps.push(step);
+            return Trajectory {
+                steps,
+                end: match transferred {
+                    true => TrajectoryEnd::Transferred,
+                    false => TrajectoryEnd::Dissolved,
+                },
+            };
+        }
+        pivot += movement;
+        cells.iter_mut().for_each(|pos| *pos += movement);
+        step.pivot = pivot;
+        steps.push(step);
+    }
+
+    Trajectory {
+        steps,
+        end: TrajectoryEnd::Endless,
+    }
+}
+
+/// The overlay that shows the predicted trajectories of the active blobs on the factory field, it is respawned
+/// whenever one of the trajectories changes, e.g. as a tool is placed or erased
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct TrajectoryOverlay {
+    pub trajectories: Vec<Trajectory>,
+}
+
+/// Keeps the [`TrajectoryOverlay`] in sync with the blobs and the tool layout, it is shown while the simulation
+/// is paused and the trajectories are enabled in the [`Settings`]
+#[allow(clippy::too_many_arguments)]
+pub fn trajectory_overlay_system(
+    mut commands: Commands,
+    overlay_query: Query<(Entity, &TrajectoryOverlay)>,
+    factory_query: Query<(Entity, &Field), With<FactoryFieldTag>>,
+    production_query: Query<&Field, With<ProductionFieldTag>>,
+    blob_query: Query<(&Blob, &GridBody)>,
+    tool_query: Query<(&Tool, &GridBody)>,
+    block_query: Query<&Block>,
+    cutter_shapes: Res<CutterShapes>,
+    kicks: Res<KickTable>,
+    settings: Res<Settings>,
+    level_state: Res<GameStateLevel>,
+    player_state: Res<PlayerStateLevel>,
+) {
+    let Ok((field_id, factory)) = factory_query.get_single() else {
+        return;
+    };
+
+    let shown = settings.show_trajectory
+        && level_state.is_paused()
+        && !player_state.won
+        && !player_state.lost;
+    let expected = shown.then(|| {
+        let state = factory.get_field_state();
+        let positions = |body: &GridBody| -> Vec<IVec2> {
+            body.blocks
+                .iter()
+                .filter_map(|id| block_query.get(*id).ok())
+                .map(|block| block.position)
+                .collect()
+        };
+        let layout = ToolLayout {
+            factory,
+            production: production_query.get_single().ok(),
+            tools: state
+                .into_iter()
+                .filter_map(|el| match el.kind {
+                    FieldElementKind::Tool(id) => {
+                        let (tool, _) = tool_query.get(id).ok()?;
+                        Some((el.position, *tool))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            cutters: tool_query
+                .iter()
+                .filter_map(|(tool, body)| match tool {
+                    Tool::Cutter(shape, _) => {
+                        let mode = cutter_shapes
+                            .get(*shape)
+                            .map(|definition| definition.mode)
+                            .unwrap_or_default();
+                        Some((positions(body), mode))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            kicks: &kicks,
+        };
+
+        TrajectoryOverlay {
+            trajectories: blob_query
+                .iter()
+                .filter(|(blob, body)| blob.active && !body.transferred)
+                .map(|(blob, body)| {
+                    predict_trajectory(&layout, body.pivot, positions(body), blob.movement)
+                })
+                .collect(),
+        }
+    });
+
+    let current = overlay_query.get_single().ok();
+    if current.map(|(_, overlay)| overlay) == expected.as_ref() {
+        return;
+    }
+    if let Some((id, _)) = current {
+        commands.entity(id).despawn_recursive();
+    }
+    if let Some(overlay) = expected {
+        spawn_trajectory_overlay(&mut commands, overlay, field_id, factory);
+    }
+}
+
+/// spawns the [`TrajectoryOverlay`] as a child of the factory field with a mark per turn, rotations and cuts are
+/// highlighted and the end of a trajectory is marked by a bigger mark
+pub fn spawn_trajectory_overlay(
+    commands: &mut Commands,
+    overlay: TrajectoryOverlay,
+    field_id: Entity,
+    field: &Field,
+) -> Entity {
+    let mut marks = vec![];
+    for trajectory in overlay.trajectories.iter() {
+        let last = trajectory.steps.len() - 1;
+        for (idx, step) in trajectory.steps.iter().enumerate() {
+            let color = if step.cut {
+                Color::rgba(1.0, 0.3, 0.3, 0.8)
+            } else if step.rotated {
+                Color::rgba(1.0, 0.7, 0.2, 0.8)
+            } else {
+                Color::rgba(1.0, 1.0, 1.0, 0.6)
+            };
+            let size = match (idx == last, trajectory.end) {
+                (true, TrajectoryEnd::Endless) | (false, _) => TRAJECTORY_MARK_SIZE,
+                (true, _) => TRAJECTORY_MARK_SIZE * 2.0,
+            };
+            let (px, py) = field.coords_to_px(step.pivot.x, step.pivot.y);
+            marks.push((Vec2::new(px, py), size, color));
+        }
+    }
+
+    let id = commands
+        .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+            0.0,
+            0.0,
+            Z_OVERLAY + 0.25,
+        )))
+        .insert(overlay)
+        .insert(Name::new("Trajectory Overlay"))
+        .with_children(|cb| {
+            for (pos, size, color) in marks {
+                cb.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(size)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(pos.extend(0.0)),
+                    ..Default::default()
+                });
+            }
+        })
+        .id();
+    commands.entity(field_id).push_children(&[id]);
+
+    id
+}
+
+#[test]
+fn test_predict_trajectory() {
+    // an empty factory field with settled blocks at the given cells
+    let factory_with = |blocks: &[IVec2]| {
+        let mut field = Field::as_factory();
+        let mut state = FieldState::new(field.bounds());
+        let (min, max) = field.bounds();
+        for x in min.x..max.x {
+            for y in min.y..max.y {
+                let pos = IVec2::new(x, y);
+                let mut element = state.get_element(pos).unwrap();
+                if x < 0
+                    || y < 0
+                    || x >= field.mov_size().0 as i32
+                    || y >= field.mov_size().1 as i32
+                {
+                    element.kind = FieldElementKind::OutOfMovableRegion;
+                } else if blocks.contains(&pos) {
+                    element.kind = FieldElementKind::Block(None);
+                }
+                state.set_element(pos, element);
+            }
+        }
+        field.set_field_state(state);
+        field
+    };
+    let factory = factory_with(&[]);
+    let kicks = KickTable::default();
+    let layout =
+        |factory, tools: &[(IVec2, Tool)], cutters: Vec<(Vec<IVec2>, CutterMode)>| ToolLayout {
+            factory,
+            production: None,
+            tools: tools.iter().copied().collect(),
+            cutters,
+            kicks: &kicks,
+        };
+    let domino = |pivot: IVec2| vec![pivot, pivot + IVec2::X];
+    let down = IVec2::new(0, 1);
+    let start = IVec2::new(4, 0);
+    let last = |trajectory: &Trajectory| trajectory.steps.last().unwrap().pivot;
+
+    // without tools the blob dissolves at the floor
+    let plain = layout(&factory, &[], vec![]);
+    let trajectory = predict_trajectory(&plain, start, domino(start), down);
+    assert_eq!(trajectory.end, TrajectoryEnd::Dissolved);
+    assert_eq!(trajectory.steps[0].pivot, start);
+    assert_eq!(last(&trajectory), IVec2::new(4, 23));
+
+    // with a production field the blob leaves the bottom of the factory
+    let mut production = Field::as_production_field();
+    production.set_field_state(FieldState::new(production.bounds()));
+    let transfer = ToolLayout {
+        production: Some(&production),
+        ..layout(&factory, &[], vec![])
+    };
+    let trajectory = predict_trajectory(&transfer, start, domino(start), down);
+    assert_eq!(trajectory.end, TrajectoryEnd::Transferred);
+    assert_eq!(last(&trajectory), IVec2::new(4, 23));
+
+    // move tools change the direction
+    let moves = layout(
+        &factory,
+        &[
+            (IVec2::new(4, 5), Tool::Move(MoveDirection::Right)),
+            (IVec2::new(7, 5), Tool::Move(MoveDirection::Down)),
+        ],
+        vec![],
+    );
+    let trajectory = predict_trajectory(&moves, start, domino(start), down);
+    assert!(trajectory
+        .steps
+        .iter()
+        .any(|step| step.pivot == IVec2::new(6, 5)));
+    assert_eq!(trajectory.end, TrajectoryEnd::Dissolved);
+    assert_eq!(last(&trajectory), IVec2::new(7, 23));
+
+    // the rotated blob would overlap the block below the rotator, it is kicked to the left
+    let blocked = factory_with(&[IVec2::new(4, 6)]);
+    let rotator = [(IVec2::new(4, 5), Tool::Rotate(RotateDirection::Right))];
+    let trajectory = predict_trajectory(
+        &layout(&blocked, &rotator, vec![]),
+        start,
+        domino(start),
+        down,
+    );
+    let rotated: Vec<_> = trajectory
+        .steps
+        .iter()
+        .filter(|step| step.rotated)
+        .collect();
+    assert_eq!(rotated.len(), 1);
+    assert_eq!(rotated[0].pivot, IVec2::new(3, 6));
+    assert_eq!(last(&trajectory), IVec2::new(3, 22));
+    // without kicks the rotation is cancelled and the blob dissolves on the block
+    let no_kicks = KickTable::without_kicks();
+    let cancelled = ToolLayout {
+        kicks: &no_kicks,
+        ..layout(&blocked, &rotator, vec![])
+    };
+    let trajectory = predict_trajectory(&cancelled, start, domino(start), down);
+    assert!(trajectory.steps.iter().all(|step| !step.rotated));
+    assert_eq!(trajectory.end, TrajectoryEnd::Dissolved);
+    assert_eq!(last(&trajectory), IVec2::new(4, 5));
+
+    // a cut removes a block and the remainder moves on, a trimmer that covers the whole blob removes it
+    let cutter = layout(
+        &factory,
+        &[],
+        vec![(vec![IVec2::new(4, 10)], CutterMode::Full)],
+    );
+    let trajectory = predict_trajectory(&cutter, start, domino(start), down);
+    let cuts: Vec<_> = trajectory.steps.iter().filter(|step| step.cut).collect();
+    assert_eq!(cuts.len(), 1);
+    assert_eq!(cuts[0].pivot, IVec2::new(4, 11));
+    assert_eq!(trajectory.end, TrajectoryEnd::Dissolved);
+    let trimmer = layout(
+        &factory,
+        &[],
+        vec![(
+            domino(IVec2::new(4, 10)),
+            CutterMode::Trimmer { threshold: 1 },
+        )],
+    );
+    let trajectory = predict_trajectory(&trimmer, start, domino(start), down);
+    assert_eq!(trajectory.end, TrajectoryEnd::Trimmed);
+    assert_eq!(last(&trajectory), IVec2::new(4, 10));
+
+    // move tools in a circle let the blob run forever
+    let circle = layout(
+        &factory,
+        &[
+            (IVec2::new(2, 2), Tool::Move(MoveDirection::Down)),
+            (IVec2::new(2, 4), Tool::Move(MoveDirection::Right)),
+            (IVec2::new(4, 4), Tool::Move(MoveDirection::Up)),
+            (IVec2::new(4, 2), Tool::Move(MoveDirection::Left)),
+        ],
+        vec![],
+    );
+    let pivot = IVec2::new(2, 2);
+    let trajectory = predict_trajectory(&circle, pivot, vec![pivot], down);
+    assert_eq!(trajectory.end, TrajectoryEnd::Endless);
+    // the loop is detected as soon as a cell is entered again with the same movement
+    assert_eq!(trajectory.steps.len(), 10);
+    assert_eq!(last(&trajectory), IVec2::new(2, 3));
+}
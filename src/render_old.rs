@@ -94,10 +94,7 @@ pub trait RenderableGrid {
                                 translation: Vec3::new(x, y, info.z),
                                 ..Default::default()
                             },
-                            texture: match info.image {
-                                Some(img) => img,
-                                None => Handle::default(),
-                            },
+                            texture: info.image.unwrap_or_default(),
                             ..Default::default()
                         });
                         ec.insert(FieldRenderTag {});
@@ -206,8 +203,12 @@ impl RenderableGrid for Field {
         let state = self.get_field_state();
         if let Some(element) = state.get_element(IVec2::new(c, r)) {
             match element.kind {
-                FieldElementKind::Empty if element.is_target => 3,
-                FieldElementKind::Empty => 0,
+                FieldElementKind::Empty => match element.target {
+                    Some(TargetCell::Required(_)) => 3,
+                    Some(TargetCell::Optional) => 4,
+                    Some(TargetCell::Forbidden) => 5,
+                    None => 0,
+                },
                 FieldElementKind::OutOfMovableRegion => 2,
                 FieldElementKind::OutOfValidRegion => -1, // nothing rendered outside of the valid region
                 FieldElementKind::Block(_) => 0,          // view.rs is responsible to render that
@@ -260,10 +261,20 @@ impl RenderableGrid for Field {
                 color: Color::RED,
                 z: Z_FIELD,
             },
+            4 => SpriteInfo {
+                image: None,
+                color: Color::SALMON,
+                z: Z_FIELD,
+            },
+            5 => SpriteInfo {
+                image: None,
+                color: Color::rgb(0.25, 0.05, 0.25),
+                z: Z_FIELD,
+            },
             _ => {
                 if let Ok(tool) = TryInto::<Tool>::try_into(num) {
                     SpriteInfo {
-                        color: Color::WHITE,
+                        color: assets.get_tool_tint(tool),
                         z: Z_FIELD,
                         image: Some(assets.get_tool_image(tool).clone()),
                     }
@@ -318,10 +329,7 @@ pub fn old_render_entities_system<T: Component + RenderableGrid>(
                         if pivot.is_some() {
                             sprite.color = Color::YELLOW;
                         }
-                        *texture = match info.image {
-                            Some(img) => img,
-                            None => Handle::default(),
-                        };
+                        *texture = info.image.unwrap_or_default();
                         t.translation.z = info.z;
                     }
                 }
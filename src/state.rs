@@ -1,7 +1,8 @@
 use crate::get_random_quote;
-use crate::{hud::spawn_text, prelude::*, DisastrisAppState};
+use crate::{hud::spawn_text, input::ToolActions, menu::MenuState, prelude::*, DisastrisAppState};
 
 use bevy::{prelude::*, utils::HashMap};
+use leafwing_input_manager::prelude::*;
 
 use crate::movement::prelude::*;
 
@@ -10,6 +11,7 @@ use crate::data::prelude::*;
 pub mod prelude {
     pub use super::GameStateLevel;
     pub use super::PlayerStateLevel;
+    pub use super::ToolEdit;
 
     pub use super::progress_level_time_system;
 }
@@ -57,6 +59,18 @@ pub struct GameStateLevel {
     pub simulation_running: bool,
 
     pub num_additional_bricks: i32,
+
+    /// the number of times a cutter has cut blocks of a blob
+    pub num_cuts: usize,
+
+    /// the number of rows that have been cleared
+    pub num_lines_cleared: u32,
+
+    /// the score that is accumulated by clearing rows
+    pub score: u32,
+
+    /// a flag that is true if the simulation shall be paused after the current turn
+    pause_after_turn: bool,
 }
 
 impl GameStateLevel {
@@ -68,6 +82,10 @@ impl GameStateLevel {
             num_turn: 0,
             num_additional_bricks: 0,
             simulation_running: true,
+            num_cuts: 0,
+            num_lines_cleared: 0,
+            score: 0,
+            pause_after_turn: false,
         }
     }
 
@@ -77,6 +95,14 @@ impl GameStateLevel {
         }
         //~
 
+        if self.new_turn && self.pause_after_turn {
+            // the single step is done
+            self.new_turn = false;
+            self.pause_after_turn = false;
+            self.simulation_running = true;
+            return;
+        }
+
         self.new_turn = false;
         self.cur_time += dt;
         if self.cur_time > self.time_per_turn {
@@ -86,12 +112,51 @@ impl GameStateLevel {
         }
     }
 
+    /// simulates a single turn as soon as possible and pauses the simulation afterwards
+    pub fn step(&mut self) {
+        self.simulation_running = false;
+        self.pause_after_turn = true;
+        self.new_turn = false;
+        self.cur_time = self.time_per_turn;
+    }
+
+    pub fn set_time_per_turn(&mut self, time_per_turn: f32) {
+        self.time_per_turn = time_per_turn;
+    }
+
+    /// true while the simulation is paused, i.e. while the (inverted) `simulation_running` flag is set
+    pub fn is_paused(&self) -> bool {
+        self.simulation_running
+    }
+
+    /// pauses or resumes the simulation
+    pub fn set_paused(&mut self, paused: bool) {
+        self.simulation_running = paused;
+    }
+
     pub fn is_new_turn(&self) -> bool {
         self.new_turn && !self.simulation_running
     }
     pub fn get_num_turn(&self) -> i32 {
         self.num_turn
     }
+
+    /// adds the score for the given number of rows that have been cleared at once
+    pub fn add_cleared_lines(&mut self, num_lines: u32) {
+        const SCORE_PER_CLEAR: [u32; 5] = [0, 100, 300, 500, 800];
+        self.num_lines_cleared += num_lines;
+        self.score += SCORE_PER_CLEAR[num_lines.min(4) as usize];
+    }
+}
+
+/// A change of the tool layout by the player, see [`PlayerStateLevel::pop_tool_edits`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum ToolEdit {
+    /// the tool has been placed with its pivot at the coordinate
+    Placed { coordinate: IVec2, tool: Tool },
+
+    /// the tool with its pivot at the coordinate has been removed
+    Erased { coordinate: IVec2, tool: Tool },
 }
 
 /// Contains the current state of the player during a level, e.g. its selected tool and a tool inventory
@@ -108,6 +173,13 @@ pub struct PlayerStateLevel {
 
     /// A flag indicating of the player has won the level
     pub won: bool,
+
+    /// A flag indicating that the player violated a constraint of the level
+    pub lost: bool,
+
+    /// the changes of the tool layout, the edits of a single player action are grouped such that they are undone
+    /// together
+    tool_history: Vec<Vec<ToolEdit>>,
 }
 
 impl PlayerStateLevel {
@@ -119,6 +191,8 @@ impl PlayerStateLevel {
             applicable_tools,
             tool_placement_coordinate: None,
             won: false,
+            lost: false,
+            tool_history: vec![],
         }
     }
 
@@ -126,13 +200,34 @@ impl PlayerStateLevel {
         self.applicable_tools = new_inventory;
     }
 
-    ///
+    /// gets the number of tools of the given kind in the inventory, `None` if the tool is not part of it
     pub fn num_in_inventory(&self, tool: Tool) -> Option<usize> {
         // ensure default variants are used
         let tool = tool.as_default_variant();
         self.applicable_tools.get(&tool).copied()
     }
 
+    /// gets the number of all tools in the inventory
+    pub fn num_tools_in_inventory(&self) -> usize {
+        self.applicable_tools.values().sum()
+    }
+
+    /// records the edits of a single player action, an empty action is not recorded
+    pub fn push_tool_edits(&mut self, edits: Vec<ToolEdit>) {
+        if !edits.is_empty() {
+            self.tool_history.push(edits);
+        }
+    }
+
+    /// takes the edits of the last player action such that they can be undone
+    pub fn pop_tool_edits(&mut self) -> Option<Vec<ToolEdit>> {
+        self.tool_history.pop()
+    }
+
+    pub fn clear_tool_edits(&mut self) {
+        self.tool_history.clear();
+    }
+
     pub fn add_to_inventory(&mut self, tool: Tool, change: i32) -> bool {
         // ensure default variants are used
         let tool = tool.as_default_variant();
@@ -152,8 +247,18 @@ impl PlayerStateLevel {
     }
 }
 
-pub fn progress_level_time_system(mut level_state: ResMut<GameStateLevel>, time: ResMut<Time>) {
-    level_state.apply_time(time.delta_seconds());
+/// progresses the time of the level, the time is frozen while the pause menu is open
+pub fn progress_level_time_system(
+    mut level_state: ResMut<GameStateLevel>,
+    time: ResMut<Time>,
+    menu_state: Res<MenuState>,
+) {
+    let dt = if menu_state.open {
+        0.0
+    } else {
+        time.delta_seconds()
+    };
+    level_state.apply_time(dt);
 }
 
 pub fn app_state_transition_system(
@@ -161,26 +266,37 @@ pub fn app_state_transition_system(
     mut next_state: ResMut<NextState<DisastrisAppState>>,
     mut gamestate: ResMut<GameState>,
     mut playerstate: ResMut<PlayerStateLevel>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<ActionState<ToolActions>>,
+    menu_state: Res<MenuState>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Return) {
+    // the continue action follows the bindings of the player, it isn't bound to the mouse by default such that a
+    // click on the field doesn't skip the won or lost level
+    if actions.just_pressed(ToolActions::Continue) {
         let did_transition = match cur_state.get() {
+            // the pause menu handles the continue action itself
+            DisastrisAppState::PlayLevel if menu_state.open => false,
             DisastrisAppState::PlayLevel => {
                 if playerstate.won {
                     let next_num = gamestate.level.as_ref().unwrap().num + 1;
                     playerstate.won = false;
 
-                    if next_num <= 3 {
+                    if next_num <= Level::NUM_LEVELS {
                         gamestate.upcoming_level = Some(crate::data::level::Level::new(next_num));
                     } else {
                         gamestate.upcoming_level = None;
                     }
                     next_state.set(DisastrisAppState::TransitionLevel);
                     true
+                } else if playerstate.lost {
+                    // From Placeholder --> PlayLevel to retry the level
+                    next_state.set(DisastrisAppState::Placeholder);
+                    true
                 } else {
                     false
                 }
             }
+            // the main menu handles the continue action itself, see [`crate::menu::menu_navigation_system`]
+            DisastrisAppState::Mainmenu => false,
             DisastrisAppState::TransitionLevel => {
                 if gamestate.upcoming_level.is_none() {
                     next_state.set(DisastrisAppState::Mainmenu);
@@ -192,7 +308,7 @@ pub fn app_state_transition_system(
             }
             _ => {
                 warn!(
-                    "No App State transition from {} yet (triggered by <RETURN>)",
+                    "No App State transition from {} yet (triggered by the continue action)",
                     cur_state.get().to_string()
                 );
                 false
@@ -278,7 +394,10 @@ pub fn spawn_transition_level(mut commands: Commands, assets: Res<GameAssets>, g
                     .unwrap_or(&0usize),
                 level
                     .applicable_tools
-                    .get(&Tool::Cutter(TetrisBricks::default()))
+                    .get(&Tool::Cutter(
+                        CutterShape::default(),
+                        Orientation::default()
+                    ))
                     .unwrap_or(&0usize),
             ),
             Vec2::new(0., -350.),
@@ -10,8 +10,10 @@ use std::{
 };
 
 use crate::{
-    data::assets::GameAssets, field::prelude::*, input::TetrisActionsWASD, DisastrisAppState,
-    PX_PER_TILE, Z_SOLID,
+    data::{assets::GameAssets, bodies::BlockColor},
+    field::prelude::*,
+    input::TetrisActionsWASD,
+    DisastrisAppState, PX_PER_TILE, Z_SOLID,
 };
 use bevy::{ecs::system::EntityCommands, log, prelude::*};
 use bevy_tweening::{lens::*, *};
@@ -37,6 +39,7 @@ pub mod prelude {
 pub enum Rotation {
     Left,
     Right,
+    Half,
 }
 
 /// Events for the Renderer
@@ -48,6 +51,8 @@ pub enum ViewUpdate {
     BlobMoved(Entity),
     /// A blob has been rotated
     BlobRotated(Entity, Rotation),
+    /// A rotation of the blob has been cancelled as the rotated blocks would overlap occupied cells
+    BlobRotationBlocked(Entity, Rotation),
     /// A new blob `entity` has been cutout. The new blob `entity` must have the
     /// blocks that originally were part of the original blob.
     BlobCutout(Entity),
@@ -55,6 +60,10 @@ pub enum ViewUpdate {
     BlobTransferred(Entity),
     /// A line of blocks was removed in the tetris field.
     LineRemove(Vec<Entity>),
+    /// The given blocks that are not part of a blob anymore have been moved down, e.g. after a line removal
+    BlocksShifted(Vec<Entity>),
+    /// The color of the given blocks has been changed, e.g. by a painter tool
+    BlocksPainted(Vec<Entity>),
 }
 
 /// Configuration struct for integration into the rest of the project
@@ -164,6 +173,7 @@ pub fn rotate_coord(coord: IVec2, rotation: Rotation) -> IVec2 {
     let rot2vec = match rotation {
         Rotation::Left => IVec2::new(0, -1),
         Rotation::Right => IVec2::new(0, 1),
+        Rotation::Half => IVec2::new(-1, 0),
     };
     rot2vec.rotate(coord)
 }
@@ -182,6 +192,8 @@ fn test_rotate_coord() {
     assert_eq!(rotate_coord(c4, Rotation::Right), c5);
     assert_eq!(rotate_coord(c3, Rotation::Left), c2);
     assert_eq!(rotate_coord(c5, Rotation::Left), c4);
+    assert_eq!(rotate_coord(c1, Rotation::Half), c1);
+    assert_eq!(rotate_coord(c4, Rotation::Half), IVec2::new(-1, 1));
 }
 
 //----------------------------------------------------------------------
@@ -246,7 +258,7 @@ fn handle_blob_spawned(
 
         commands.entity(block).insert(SpriteBundle {
             sprite: Sprite {
-                color: Color::WHITE,
+                color: blockdata.color.tint(),
                 custom_size: Some(Vec2::ONE * PX_PER_TILE),
                 ..Default::default()
             },
@@ -302,6 +314,7 @@ fn handle_blob_rotated(
         let end = match rotation {
             Rotation::Left => ((state.rotation_steps + 1) as f32 * 90.0).to_radians(),
             Rotation::Right => ((state.rotation_steps - 1) as f32 * 90.0).to_radians(),
+            Rotation::Half => ((state.rotation_steps + 2) as f32 * 90.0).to_radians(),
         };
 
         /*
@@ -322,10 +335,83 @@ fn handle_blob_rotated(
             Rotation::Right => {
                 state.rotation_steps = (state.rotation_steps - 1 + 4) % 4;
             }
+            Rotation::Half => {
+                state.rotation_steps = (state.rotation_steps + 2) % 4;
+            }
         }
     }
 }
 
+fn handle_blob_rotation_blocked(
+    commands: &mut Commands,
+    blob: Entity,
+    rotation: Rotation,
+    blob_query: &mut Query<(&GridBody, &mut BlobRenderState)>,
+    block_query: &Query<&Block>,
+    config: &Res<ViewConfig>,
+) {
+    if let Ok((bodydata, mut state)) = blob_query.get_mut(blob) {
+        // wiggle a bit into the direction of the rotation and back to the current angle
+        let end = (state.rotation_steps as f32 * 90.0).to_radians();
+        let wiggle = match rotation {
+            Rotation::Left | Rotation::Half => 15.0_f32.to_radians(),
+            Rotation::Right => -15.0_f32.to_radians(),
+        };
+        state
+            .rotation_tween
+            .set(end + wiggle, end, config.anim_duration);
+
+        for &block in bodydata.blocks.iter() {
+            let tint = block_tint(block, block_query);
+            let tween = Tween::new(
+                EaseFunction::QuadraticInOut,
+                config.anim_duration / 2,
+                SpriteColorLens {
+                    start: tint,
+                    end: Color::RED,
+                },
+            )
+            .then(Tween::new(
+                EaseFunction::QuadraticInOut,
+                config.anim_duration / 2,
+                SpriteColorLens {
+                    start: Color::RED,
+                    end: tint,
+                },
+            ));
+            commands.entity(block).insert(Animator::new(tween));
+        }
+    }
+}
+
+fn handle_blocks_painted(
+    commands: &mut Commands,
+    blocks: &[Entity],
+    block_query: &Query<&Block>,
+    config: &Res<ViewConfig>,
+) {
+    for &block in blocks.iter() {
+        let tween = Tween::new(
+            EaseFunction::QuadraticInOut,
+            config.anim_duration,
+            SpriteColorLens {
+                start: Color::WHITE,
+                end: block_tint(block, block_query),
+            },
+        );
+        commands.entity(block).insert(Animator::new(tween));
+    }
+}
+
+/// gets the tint of the sprite of a block that depends on its color
+fn block_tint(block: Entity, block_query: &Query<&Block>) -> Color {
+    block_query
+        .get(block)
+        .map(|blockdata| blockdata.color)
+        .unwrap_or_default()
+        .tint()
+}
+
 fn handle_blob_cutout(
     commands: &mut Commands,
     newblob: Entity,
@@ -349,7 +435,8 @@ fn handle_blob_cutout(
             config.anim_duration,
             SpriteColorLens {
                 start: Color::BLUE,
-                end: Color::GRAY,
+                // the cut blocks keep their color
+                end: block_tint(block, block_query),
             },
         ));
         commands.entity(block).insert(Animator::new(tween));
@@ -396,17 +483,57 @@ fn handle_line_remove(
             EaseFunction::QuadraticInOut,
             config.anim_duration,
             SpriteColorLens { start, end },
-        );
+        )
+        .with_completed_event(TWEEN_DESPAWN_BLOCK);
         commands.entity(block).insert(Animator::new(tween));
     }
 }
 
+fn handle_blocks_shifted(
+    commands: &mut Commands,
+    blocks: &[Entity],
+    block_query: &Query<&Block>,
+    production_query: &Query<(), With<ProductionFieldTag>>,
+    config: &Res<ViewConfig>,
+) {
+    for &block in blocks.iter() {
+        if let Ok(blockdata) = block_query.get(block) {
+            let topleft = if production_query.contains(blockdata.field) {
+                config.tetris_topleft
+            } else {
+                config.factory_topleft
+            };
+            // the parent blob of dissolved blocks is gone, so the blocks are positioned in the global frame
+            let translation = topleft + coord_to_translation(blockdata.position);
+            commands
+                .entity(block)
+                .remove::<Parent>()
+                .insert(Transform::from_translation(translation));
+        }
+    }
+}
+
+/// user data of the tweens whose entity shall be despawned when the tween has completed
+const TWEEN_DESPAWN_BLOCK: u64 = 1;
+
+/// despawns the blocks whose removal animation has completed
+pub fn despawn_removed_blocks_system(mut commands: Commands, mut ev: EventReader<TweenCompleted>) {
+    for ev in ev.read() {
+        if ev.user_data == TWEEN_DESPAWN_BLOCK {
+            if let Some(mut ec) = commands.get_entity(ev.entity) {
+                ec.despawn();
+            }
+        }
+    }
+}
+
 pub fn handle_view_update_system(
     mut commands: Commands,
     mut ev: EventReader<ViewUpdate>,
     blob_query: Query<&GridBody>,
     block_query: Query<&Block>,
     mut rendered_blobs: Query<(&GridBody, &mut BlobRenderState)>,
+    production_query: Query<(), With<ProductionFieldTag>>,
     config: Res<ViewConfig>,
 ) {
     for ev in ev.read() {
@@ -420,6 +547,14 @@ pub fn handle_view_update_system(
             ViewUpdate::BlobRotated(blob, rotation) => {
                 handle_blob_rotated(&mut commands, blob, rotation, &mut rendered_blobs, &config)
             }
+            ViewUpdate::BlobRotationBlocked(blob, rotation) => handle_blob_rotation_blocked(
+                &mut commands,
+                blob,
+                rotation,
+                &mut rendered_blobs,
+                &block_query,
+                &config,
+            ),
             ViewUpdate::BlobCutout(newblob) => {
                 handle_blob_cutout(&mut commands, newblob, &blob_query, &block_query, &config)
             }
@@ -429,6 +564,16 @@ pub fn handle_view_update_system(
             ViewUpdate::LineRemove(ref blocks) => {
                 handle_line_remove(&mut commands, blocks, &block_query, &config)
             }
+            ViewUpdate::BlocksShifted(ref blocks) => handle_blocks_shifted(
+                &mut commands,
+                blocks,
+                &block_query,
+                &production_query,
+                &config,
+            ),
+            ViewUpdate::BlocksPainted(ref blocks) => {
+                handle_blocks_painted(&mut commands, blocks, &block_query, &config)
+            }
         }
     }
 }
@@ -461,6 +606,7 @@ fn spawn_demo_blob(commands: &mut Commands) -> Entity {
                             position: coord,
                             group: None,
                             field: field_id,
+                            color: BlockColor::default(),
                         })
                         .id(),
                 );
@@ -478,7 +624,6 @@ fn spawn_demo_blob(commands: &mut Commands) -> Entity {
         .insert(Blob {
             movement: IVec2::ZERO, //IVec2::new(-1, 4),
             active: true,
-            cutout: false,
         })
         .insert(Name::new("Test Blob"))
         .id()
@@ -662,7 +807,6 @@ fn cutout_triangle_from_blob(
             .insert(Blob {
                 movement: IVec2::ZERO,
                 active: true,
-                cutout: true,
             })
             .id();
         Some(newblob)